@@ -13,6 +13,17 @@ impl EnvVars {
     pub const PREFLIGIT_HOME: &'static str = "PREFLIGIT_HOME";
     pub const PREFLIGIT_ALLOW_NO_CONFIG: &'static str = "PREFLIGIT_ALLOW_NO_CONFIG";
     pub const PREFLIGIT_NO_CONCURRENCY: &'static str = "PREFLIGIT_NO_CONCURRENCY";
+    pub const PREFLIGIT_PTY: &'static str = "PREFLIGIT_PTY";
+    pub const PREFLIGIT_OFFLINE: &'static str = "PREFLIGIT_OFFLINE";
+    pub const PREFLIGIT_SETTINGS: &'static str = "PREFLIGIT_SETTINGS";
+    pub const PREFLIGIT_REPO_MIRRORS: &'static str = "PREFLIGIT_REPO_MIRRORS";
+    pub const PREFLIGIT_NODE_MIRROR: &'static str = "PREFLIGIT_NODE_MIRROR";
+    pub const PREFLIGIT_PYTHON_MIRROR: &'static str = "PREFLIGIT_PYTHON_MIRROR";
+    pub const PREFLIGIT_UV_MIRROR: &'static str = "PREFLIGIT_UV_MIRROR";
+    pub const PREFLIGIT_PROXY: &'static str = "PREFLIGIT_PROXY";
+    pub const PREFLIGIT_NO_PROXY: &'static str = "PREFLIGIT_NO_PROXY";
+    pub const PREFLIGIT_CA_CERT: &'static str = "PREFLIGIT_CA_CERT";
+    pub const PREFLIGIT_STRICT_REVS: &'static str = "PREFLIGIT_STRICT_REVS";
 
     // Prefligit internal environment variables
     pub const PREFLIGIT_INTERNAL__TEST_DIR: &'static str = "PREFLIGIT_INTERNAL__TEST_DIR";
@@ -20,10 +31,24 @@ impl EnvVars {
         "PREFLIGIT_INTERNAL__SORT_FILENAMES";
     pub const PREFLIGIT_INTERNAL__SKIP_POST_CHECKOUT: &'static str =
         "PREFLIGIT_INTERNAL__SKIP_POST_CHECKOUT";
+    pub const PREFLIGIT_INTERNAL__RUNNING_LEGACY: &'static str =
+        "PREFLIGIT_INTERNAL__RUNNING_LEGACY";
+    pub const PREFLIGIT_INTERNAL__MAX_CLI_LENGTH: &'static str =
+        "PREFLIGIT_INTERNAL__MAX_CLI_LENGTH";
 
     // Other environment variables
+    pub const CLICOLOR_FORCE: &'static str = "CLICOLOR_FORCE";
+    pub const FORCE_COLOR: &'static str = "FORCE_COLOR";
+    pub const GIT_INDEX_FILE: &'static str = "GIT_INDEX_FILE";
+    pub const GIT_TERMINAL_PROMPT: &'static str = "GIT_TERMINAL_PROMPT";
+    pub const HTTP_PROXY: &'static str = "HTTP_PROXY";
+    pub const HTTPS_PROXY: &'static str = "HTTPS_PROXY";
+    pub const NO_PROXY: &'static str = "NO_PROXY";
+    pub const SSL_CERT_FILE: &'static str = "SSL_CERT_FILE";
     pub const UV_NO_CACHE: &'static str = "UV_NO_CACHE";
+    pub const UV_OFFLINE: &'static str = "UV_OFFLINE";
     pub const UV_PYTHON_INSTALL_DIR: &'static str = "UV_PYTHON_INSTALL_DIR";
+    pub const UV_PYTHON_INSTALL_MIRROR: &'static str = "UV_PYTHON_INSTALL_MIRROR";
 }
 
 impl EnvVars {
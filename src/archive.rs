@@ -25,9 +25,10 @@ use std::fmt::{Display, Formatter};
 use std::path::{Component, Path, PathBuf};
 
 use async_compression::tokio::bufread::{GzipDecoder, XzDecoder};
+use async_compression::tokio::write::GzipEncoder;
 use async_zip::base::read::stream::ZipFileReader;
-use tokio::io::{AsyncRead, BufReader};
-use tokio_tar::ArchiveBuilder;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio_tar::{ArchiveBuilder, Builder};
 use tokio_util::compat::FuturesAsyncReadCompatExt;
 use tracing::warn;
 
@@ -304,3 +305,26 @@ pub async fn unpack<R: AsyncRead + Unpin>(
         _ => Err(Error::UnsupportedArchive(target.as_ref().to_path_buf())),
     }
 }
+
+/// Pack files and directories into a `.tar.gz` archive, each as the name it is paired with.
+///
+/// Symlinks are kept as symlinks, so they still point to the same paths once unpacked.
+pub async fn tar_gz<W: AsyncWrite + Unpin + Send>(
+    writer: W,
+    entries: &[(PathBuf, PathBuf)],
+) -> Result<(), Error> {
+    let mut builder = Builder::new_non_terminated(GzipEncoder::new(writer));
+    builder.follow_symlinks(false);
+
+    for (name, path) in entries {
+        if path.is_dir() {
+            builder.append_dir_all(name, path).await?;
+        } else {
+            builder.append_path_with_name(path, name).await?;
+        }
+    }
+
+    let mut encoder = builder.into_inner().await?;
+    encoder.shutdown().await?;
+    Ok(())
+}
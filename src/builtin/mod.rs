@@ -1,19 +1,21 @@
 use std::collections::HashMap;
-use std::str::FromStr;
 
-use crate::builtin::pre_commit_hooks::{Implemented, is_pre_commit_hooks};
 use crate::hook::{Hook, Repo};
 
 mod meta_hooks;
 mod pre_commit_hooks;
 
+pub(crate) use pre_commit_hooks::is_pre_commit_hooks;
+
 /// Returns true if the hook has a builtin Rust implementation.
 pub fn check_fast_path(hook: &Hook) -> bool {
     match hook.repo() {
         Repo::Meta { .. } => true,
-        Repo::Remote { url, .. } if is_pre_commit_hooks(url) => {
-            Implemented::from_str(hook.id.as_str()).is_ok()
-        }
+        Repo::Remote {
+            pre_commit_hooks: true,
+            rev,
+            ..
+        } => pre_commit_hooks::find(hook, rev).is_some(),
         _ => false,
     }
 }
@@ -25,8 +27,12 @@ pub async fn run_fast_path(
 ) -> anyhow::Result<(i32, Vec<u8>)> {
     match hook.repo() {
         Repo::Meta { .. } => run_meta_hook(hook, filenames, env_vars).await,
-        Repo::Remote { url, .. } if is_pre_commit_hooks(url) => {
-            Implemented::from_str(hook.id.as_str())
+        Repo::Remote {
+            pre_commit_hooks: true,
+            rev,
+            ..
+        } => {
+            pre_commit_hooks::find(hook, rev)
                 .unwrap()
                 .run(hook, filenames, env_vars)
                 .await
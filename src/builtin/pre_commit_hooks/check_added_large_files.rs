@@ -20,10 +20,11 @@ impl FileFilter {
 }
 
 #[derive(Parser)]
-struct Args {
+pub(crate) struct Args {
     #[arg(long)]
     enforce_all: bool,
-    #[arg(default_value = "500")]
+    /// Maximum allowable KB for added files.
+    #[arg(long = "maxkb", default_value = "500")]
     max_kb: u64,
 }
 
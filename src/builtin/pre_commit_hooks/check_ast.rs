@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures::StreamExt;
+
+use crate::builtin::pre_commit_hooks::python;
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+/// Checks that files parse as valid Python.
+pub(crate) async fn check_ast(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let content = fs_err::tokio::read(filename).await?;
+
+            if let Err(err) = python::parse(&content) {
+                let output = format!(
+                    "{filename}:{}:{}: failed parsing: {}\n",
+                    err.line, err.col, err.message
+                );
+                anyhow::Ok((1, output.into_bytes()))
+            } else {
+                anyhow::Ok((0, Vec::new()))
+            }
+        })
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let (c, o) = result?;
+        code |= c;
+        output.extend(o);
+    }
+
+    Ok((code, output))
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures::StreamExt;
+use rustpython_parser::{Mode, Tok, lexer};
+
+use crate::builtin::pre_commit_hooks::python;
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+/// Checks that the module docstring, if any, comes before any code.
+pub(crate) async fn check_docstring_first(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let content = fs_err::tokio::read(filename).await?;
+
+            if let Some(message) = check_docstring(&content) {
+                anyhow::Ok((1, format!("{filename}:{message}\n").into_bytes()))
+            } else {
+                anyhow::Ok((0, Vec::new()))
+            }
+        })
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let (c, o) = result?;
+        code |= c;
+        output.extend(o);
+    }
+
+    Ok((code, output))
+}
+
+/// Returns the problem with the module docstring, prefixed with its location.
+///
+/// Like the original, any string token starting at the first column counts as a docstring.
+fn check_docstring(source: &[u8]) -> Option<String> {
+    // Like `tokenize`, stop at the first error.
+    let Ok(source) = python::decode(source) else {
+        return None;
+    };
+
+    let mut found_docstring_line = None;
+    let mut found_code_line = None;
+
+    for token in lexer::lex(source, Mode::Module) {
+        let Ok((token, range)) = token else {
+            break;
+        };
+        let (line, col) = python::location(source, range.start());
+        match token {
+            Tok::String { .. } if col == 0 => {
+                if let Some(docstring_line) = found_docstring_line {
+                    return Some(format!(
+                        "{line}:{col}: Multiple module docstrings (first docstring on line {docstring_line})."
+                    ));
+                } else if let Some(code_line) = found_code_line {
+                    return Some(format!(
+                        "{line}:{col}: Module docstring appears after code (code seen on line {code_line})."
+                    ));
+                }
+                found_docstring_line = Some(line);
+            }
+            Tok::Comment(_) | Tok::Newline | Tok::NonLogicalNewline | Tok::EndOfFile => {}
+            _ => {
+                found_code_line.get_or_insert(line);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_docstring;
+
+    fn check(source: &str) -> Option<String> {
+        check_docstring(source.as_bytes())
+    }
+
+    #[test]
+    fn docstring_first() {
+        assert_eq!(check(""), None);
+        assert_eq!(check("# comment\n\"\"\"docstring\"\"\"\nimport os\n"), None);
+        assert_eq!(
+            check("import os\n\ndef f():\n    \"\"\"docstring\"\"\"\n"),
+            None
+        );
+        assert_eq!(
+            check("import os\n\"\"\"docstring\"\"\"\n").as_deref(),
+            Some("2:0: Module docstring appears after code (code seen on line 1).")
+        );
+        assert_eq!(
+            check("'''one'''\n\n'''two'''\n").as_deref(),
+            Some("3:0: Multiple module docstrings (first docstring on line 1).")
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::git::ls_files_stage;
+use crate::hook::Hook;
+use crate::identify::parse_shebang;
+
+/// Checks that files marked executable in the git index have a valid shebang.
+pub(crate) async fn check_executables_have_shebangs(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    // Use the mode recorded in the index, the filesystem may not track the executable bit.
+    let entries = ls_files_stage(filenames).await?;
+
+    let mut code = 0;
+    let mut output = String::new();
+
+    for entry in entries.iter().filter(|entry| entry.is_executable()) {
+        if parse_shebang(Path::new(&entry.path)).is_ok() {
+            continue;
+        }
+
+        code = 1;
+        let path = shlex::try_quote(&entry.path).unwrap_or_else(|_| entry.path.as_str().into());
+        writeln!(
+            output,
+            "{}: marked executable but has no (or invalid) shebang!",
+            entry.path
+        )?;
+        writeln!(
+            output,
+            "  If it isn't supposed to be executable, try: `chmod -x {path}`"
+        )?;
+        writeln!(
+            output,
+            "  If on Windows, you may also need to: `git update-index --chmod=-x {path}`"
+        )?;
+        writeln!(
+            output,
+            "  If it is supposed to be executable, double-check its shebang."
+        )?;
+    }
+
+    Ok((code, output.into_bytes()))
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::git::ls_files_stage;
+use crate::hook::Hook;
+use crate::identify::{ShebangError, parse_shebang};
+
+/// Checks that files with a shebang are marked executable in the git index.
+pub(crate) async fn check_shebang_scripts_are_executable(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    // Use the mode recorded in the index, the filesystem may not track the executable bit.
+    let entries = ls_files_stage(filenames).await?;
+
+    let mut code = 0;
+    let mut output = String::new();
+
+    for entry in entries.iter().filter(|entry| !entry.is_executable()) {
+        // A shebang that fails to parse is still a shebang.
+        if matches!(
+            parse_shebang(Path::new(&entry.path)),
+            Err(ShebangError::NoShebang | ShebangError::IoError(_))
+        ) {
+            continue;
+        }
+
+        code = 1;
+        let path = shlex::try_quote(&entry.path).unwrap_or_else(|_| entry.path.as_str().into());
+        writeln!(
+            output,
+            "{}: has a shebang but is not marked executable!",
+            entry.path
+        )?;
+        writeln!(
+            output,
+            "  If it is supposed to be executable, try: `chmod +x {path}`"
+        )?;
+        writeln!(
+            output,
+            "  If on Windows, you may also need to: `git update-index --chmod=+x {path}`"
+        )?;
+        writeln!(
+            output,
+            "  If it is not supposed to be executable, double-check its shebang is wanted."
+        )?;
+    }
+
+    Ok((code, output.into_bytes()))
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::Result;
+use futures::StreamExt;
+use rustpython_ast::{self as ast, Ranged, Visitor};
+
+use crate::builtin::pre_commit_hooks::python;
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+const DEBUG_STATEMENTS: &[&str] = &[
+    "bpdb",
+    "ipdb",
+    "pdb",
+    "pdbr",
+    "pudb",
+    "pydevd_pycharm",
+    "q",
+    "rdb",
+    "rpdb",
+    "wdb",
+];
+
+/// A debugger import or `breakpoint()` call.
+#[derive(Debug, PartialEq, Eq)]
+struct Debug {
+    /// 1-based line number.
+    line: usize,
+    /// 0-based column offset, like Python's `col_offset`.
+    col: usize,
+    name: String,
+    reason: &'static str,
+}
+
+/// Checks for debugger imports and `breakpoint()` calls in Python source.
+pub(crate) async fn debug_statements(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let content = fs_err::tokio::read(filename).await?;
+
+            let mut output = String::new();
+            let suite = match python::parse(&content) {
+                Ok(suite) => suite,
+                Err(err) => {
+                    writeln!(output, "{filename} - Could not parse ast")?;
+                    writeln!(output)?;
+                    writeln!(
+                        output,
+                        "\t{filename}:{}:{}: {}",
+                        err.line, err.col, err.message
+                    )?;
+                    writeln!(output)?;
+                    return anyhow::Ok((1, output.into_bytes()));
+                }
+            };
+
+            // `parse` already checked the source decodes.
+            let source = python::decode(&content).unwrap_or_default();
+            let debugs = find_debug_statements(suite, source);
+            for debug in &debugs {
+                writeln!(
+                    output,
+                    "{filename}:{}:{}: {} {}",
+                    debug.line, debug.col, debug.name, debug.reason
+                )?;
+            }
+
+            anyhow::Ok((i32::from(!debugs.is_empty()), output.into_bytes()))
+        })
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let (c, o) = result?;
+        code |= c;
+        output.extend(o);
+    }
+
+    Ok((code, output))
+}
+
+fn find_debug_statements(suite: ast::Suite, source: &str) -> Vec<Debug> {
+    let mut finder = DebugFinder {
+        source,
+        debugs: Vec::new(),
+    };
+    for stmt in suite {
+        finder.visit_stmt(stmt);
+    }
+    finder.debugs
+}
+
+/// Collects the debug statements while visiting the AST, like the original's `ast.NodeVisitor`.
+struct DebugFinder<'a> {
+    source: &'a str,
+    debugs: Vec<Debug>,
+}
+
+impl DebugFinder<'_> {
+    fn push(&mut self, node: &impl Ranged, name: String, reason: &'static str) {
+        let (line, col) = python::location(self.source, node.start());
+        self.debugs.push(Debug {
+            line,
+            col,
+            name,
+            reason,
+        });
+    }
+}
+
+impl Visitor for DebugFinder<'_> {
+    fn visit_stmt_import(&mut self, node: ast::StmtImport) {
+        for alias in &node.names {
+            if DEBUG_STATEMENTS.contains(&alias.name.as_str()) {
+                self.push(&node, alias.name.to_string(), "imported");
+            }
+        }
+    }
+
+    fn visit_stmt_import_from(&mut self, node: ast::StmtImportFrom) {
+        // Relative imports like `from .pdb import x` have the module `pdb`.
+        if let Some(module) = &node.module {
+            if DEBUG_STATEMENTS.contains(&module.as_str()) {
+                self.push(&node, module.to_string(), "imported");
+            }
+        }
+    }
+
+    fn visit_expr_call(&mut self, node: ast::ExprCall) {
+        if let ast::Expr::Name(name) = &*node.func {
+            if name.id.as_str() == "breakpoint" {
+                self.push(&node, name.id.to_string(), "called");
+            }
+        }
+        self.generic_visit_expr_call(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_debug_statements;
+    use crate::builtin::pre_commit_hooks::python;
+
+    fn debugs(source: &str) -> Vec<String> {
+        let suite = python::parse(source.as_bytes()).unwrap();
+        find_debug_statements(suite, source)
+            .into_iter()
+            .map(|d| format!("{}:{}: {} {}", d.line, d.col, d.name, d.reason))
+            .collect()
+    }
+
+    #[test]
+    fn debug_statements() {
+        assert!(debugs("import os\nfrom foo import pdb\nimport pdbx\n").is_empty());
+        assert_eq!(
+            debugs(indoc::indoc! {r"
+                import os, pdb
+                from ipdb import set_trace
+                from .pudb import x
+                import q as debug
+
+                def f():
+                    breakpoint()
+                    foo.breakpoint()
+            "}),
+            vec![
+                "1:0: pdb imported",
+                "2:0: ipdb imported",
+                "3:0: pudb imported",
+                "4:0: q imported",
+                "7:4: breakpoint called",
+            ]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use clap::Parser;
+use futures::StreamExt;
+
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+#[derive(Parser)]
+pub(crate) struct Args {
+    /// Fold lower case to upper case characters.
+    #[arg(long)]
+    ignore_case: bool,
+    /// Ensure each line is unique.
+    #[arg(long)]
+    unique: bool,
+}
+
+pub(crate) async fn file_contents_sorter(
+    hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let entry = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
+    let args = Args::try_parse_from(entry.iter().chain(&hook.args))?;
+
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let content = fs_err::tokio::read(filename).await?;
+
+            if let Some(sorted) = sort_lines(&content, args.ignore_case, args.unique) {
+                fs_err::tokio::write(filename, &sorted).await?;
+                anyhow::Ok((1, format!("Fixing {filename}\n").into_bytes()))
+            } else {
+                anyhow::Ok((0, Vec::new()))
+            }
+        })
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let (c, o) = result?;
+        code |= c;
+        output.extend(o);
+    }
+
+    Ok((code, output))
+}
+
+/// Sort the non-blank lines of `content`, returns `None` if it is already sorted.
+fn sort_lines(content: &[u8], ignore_case: bool, unique: bool) -> Option<Vec<u8>> {
+    let mut lines: Vec<_> = content
+        .split_inclusive(|&b| b == b'\n')
+        .filter(|line| !line.trim_ascii().is_empty())
+        .map(|line| line.strip_suffix(b"\n").unwrap_or(line))
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect();
+
+    if unique {
+        lines.sort_unstable();
+        lines.dedup();
+    }
+    if ignore_case {
+        lines.sort_by_cached_key(|line| line.to_ascii_lowercase());
+    } else {
+        lines.sort();
+    }
+
+    let mut sorted = lines.join(&b'\n');
+    if !sorted.is_empty() {
+        sorted.push(b'\n');
+    }
+
+    if sorted == content {
+        None
+    } else {
+        Some(sorted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sort_lines;
+
+    #[test]
+    fn sort() {
+        assert_eq!(sort_lines(b"", false, false), None);
+        assert_eq!(sort_lines(b"a\nb\n", false, false), None);
+        assert_eq!(
+            sort_lines(b"b\n\na\r\n", false, false),
+            Some(b"a\nb\n".to_vec())
+        );
+        assert_eq!(sort_lines(b"a\nb", false, false), Some(b"a\nb\n".to_vec()));
+        assert_eq!(
+            sort_lines(b"b\nA\na\n", false, false),
+            Some(b"A\na\nb\n".to_vec())
+        );
+        assert_eq!(
+            sort_lines(b"b\na\nA\n", true, false),
+            Some(b"a\nA\nb\n".to_vec())
+        );
+        assert_eq!(
+            sort_lines(b"b\na\nb\n", false, true),
+            Some(b"a\nb\n".to_vec())
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures::StreamExt;
+
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+pub(crate) async fn fix_byte_order_marker(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let content = fs_err::tokio::read(filename).await?;
+
+            if let Some(stripped) = content.strip_prefix(BOM) {
+                fs_err::tokio::write(filename, stripped).await?;
+                anyhow::Ok((1, format!("Fixing {filename}\n").into_bytes()))
+            } else {
+                anyhow::Ok((0, Vec::new()))
+            }
+        })
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let (c, o) = result?;
+        code |= c;
+        output.extend(o);
+    }
+
+    Ok((code, output))
+}
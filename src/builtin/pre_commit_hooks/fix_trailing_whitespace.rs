@@ -9,7 +9,7 @@ use clap::Parser;
 use futures::StreamExt;
 
 #[derive(Parser)]
-struct Args {
+pub(crate) struct Args {
     #[arg(long)]
     markdown_linebreak_ext: Vec<String>,
     #[arg(long)]
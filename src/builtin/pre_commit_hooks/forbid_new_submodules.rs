@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::git::get_added_submodules;
+use crate::hook::Hook;
+
+pub(crate) async fn forbid_new_submodules(
+    _hook: &Hook,
+    filenames: &[&String],
+    env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    // Check the pushed range if running from `pre-push` or `--from-ref/--to-ref`,
+    // otherwise the staged changes.
+    let refs = match (
+        env_vars.get("PRE_COMMIT_FROM_REF"),
+        env_vars.get("PRE_COMMIT_TO_REF"),
+    ) {
+        (Some(from_ref), Some(to_ref)) => Some((from_ref.as_str(), to_ref.as_str())),
+        _ => None,
+    };
+
+    let submodules = get_added_submodules(refs, filenames).await?;
+    if submodules.is_empty() {
+        return Ok((0, Vec::new()));
+    }
+
+    let mut output = String::new();
+    for submodule in &submodules {
+        writeln!(output, "{submodule}: new submodule introduced")?;
+    }
+    writeln!(output)?;
+    writeln!(output, "This commit introduces new submodules.")?;
+    writeln!(output, "Did you unintentionally `git add .`?")?;
+    writeln!(
+        output,
+        "To fix: git rm {{thesubmodule}}  # no trailing slash"
+    )?;
+    writeln!(output, "Also check .gitmodules")?;
+
+    Ok((1, output.into_bytes()))
+}
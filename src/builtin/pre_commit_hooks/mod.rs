@@ -2,16 +2,44 @@ use std::collections::HashMap;
 use std::str::FromStr;
 
 use anyhow::Result;
+use clap::Parser;
+use semver::Version;
+use tracing::debug;
 use url::Url;
 
 use crate::hook::Hook;
 
 mod check_added_large_files;
+mod check_ast;
+mod check_docstring_first;
+mod check_executables_have_shebangs;
+mod check_shebang_scripts_are_executable;
+mod debug_statements;
+mod file_contents_sorter;
+mod fix_byte_order_marker;
 mod fix_trailing_whitespace;
+mod forbid_new_submodules;
+mod name_tests_test;
+mod no_commit_to_branch;
+mod python;
+mod requirements_txt_fixer;
+mod sort_simple_yaml;
 
 pub(crate) enum Implemented {
     TrailingWhitespace,
     CheckAddedLargeFiles,
+    CheckExecutablesHaveShebangs,
+    CheckShebangScriptsAreExecutable,
+    NoCommitToBranch,
+    ForbidNewSubmodules,
+    FixByteOrderMarker,
+    RequirementsTxtFixer,
+    FileContentsSorter,
+    SortSimpleYaml,
+    CheckAst,
+    DebugStatements,
+    CheckDocstringFirst,
+    NameTestsTest,
 }
 
 impl FromStr for Implemented {
@@ -21,6 +49,18 @@ impl FromStr for Implemented {
         match s {
             "trailing-whitespace" => Ok(Self::TrailingWhitespace),
             "check-added-large-files" => Ok(Self::CheckAddedLargeFiles),
+            "check-executables-have-shebangs" => Ok(Self::CheckExecutablesHaveShebangs),
+            "check-shebang-scripts-are-executable" => Ok(Self::CheckShebangScriptsAreExecutable),
+            "no-commit-to-branch" => Ok(Self::NoCommitToBranch),
+            "forbid-new-submodules" => Ok(Self::ForbidNewSubmodules),
+            "fix-byte-order-marker" => Ok(Self::FixByteOrderMarker),
+            "requirements-txt-fixer" => Ok(Self::RequirementsTxtFixer),
+            "file-contents-sorter" => Ok(Self::FileContentsSorter),
+            "sort-simple-yaml" => Ok(Self::SortSimpleYaml),
+            "check-ast" => Ok(Self::CheckAst),
+            "debug-statements" => Ok(Self::DebugStatements),
+            "check-docstring-first" => Ok(Self::CheckDocstringFirst),
+            "name-tests-test" => Ok(Self::NameTestsTest),
             _ => Err(()),
         }
     }
@@ -40,11 +80,248 @@ impl Implemented {
             Self::CheckAddedLargeFiles => {
                 check_added_large_files::check_added_large_files(hook, filenames, env_vars).await
             }
+            Self::CheckExecutablesHaveShebangs => {
+                check_executables_have_shebangs::check_executables_have_shebangs(
+                    hook, filenames, env_vars,
+                )
+                .await
+            }
+            Self::CheckShebangScriptsAreExecutable => {
+                check_shebang_scripts_are_executable::check_shebang_scripts_are_executable(
+                    hook, filenames, env_vars,
+                )
+                .await
+            }
+            Self::NoCommitToBranch => {
+                no_commit_to_branch::no_commit_to_branch(hook, filenames, env_vars).await
+            }
+            Self::ForbidNewSubmodules => {
+                forbid_new_submodules::forbid_new_submodules(hook, filenames, env_vars).await
+            }
+            Self::FixByteOrderMarker => {
+                fix_byte_order_marker::fix_byte_order_marker(hook, filenames, env_vars).await
+            }
+            Self::RequirementsTxtFixer => {
+                requirements_txt_fixer::requirements_txt_fixer(hook, filenames, env_vars).await
+            }
+            Self::FileContentsSorter => {
+                file_contents_sorter::file_contents_sorter(hook, filenames, env_vars).await
+            }
+            Self::SortSimpleYaml => {
+                sort_simple_yaml::sort_simple_yaml(hook, filenames, env_vars).await
+            }
+            Self::CheckAst => check_ast::check_ast(hook, filenames, env_vars).await,
+            Self::DebugStatements => {
+                debug_statements::debug_statements(hook, filenames, env_vars).await
+            }
+            Self::CheckDocstringFirst => {
+                check_docstring_first::check_docstring_first(hook, filenames, env_vars).await
+            }
+            Self::NameTestsTest => {
+                name_tests_test::name_tests_test(hook, filenames, env_vars).await
+            }
         }
     }
 }
 
-// TODO: compare rev
-pub(crate) fn is_pre_commit_hooks(url: &Url) -> bool {
-    url.host_str() == Some("github.com") && url.path() == "/pre-commit/pre-commit-hooks"
+/// The latest `pre-commit-hooks` release the builtin hooks were checked against.
+///
+/// Later releases use the Python hook until the builtin hooks are checked against them.
+const LATEST_VERIFIED: Version = Version::new(5, 0, 0);
+
+/// The commits of `pre-commit-hooks` releases, so revs frozen to a release commit, e.g. by
+/// `pre-commit autoupdate --freeze` or for strict revs, still use the builtin hooks.
+const RELEASE_COMMITS: &[(&str, Version)] = &[
+    (
+        "cef0300fd0fc4d2a87a85fa2093c6b283ea36f4b",
+        Version::new(5, 0, 0),
+    ),
+    (
+        "2c9f875913ee60ca25ce70243dc24d5b6415598c",
+        Version::new(4, 6, 0),
+    ),
+    (
+        "c4a0b883114b00d8d76b479c820ce7950211c99b",
+        Version::new(4, 5, 0),
+    ),
+    (
+        "f71fa2c1f9cf5cb705f73dffe4b21f7c61470ba9",
+        Version::new(4, 4, 0),
+    ),
+];
+
+impl Implemented {
+    /// The first `pre-commit-hooks` release this implementation is faithful to.
+    fn min_rev(&self) -> Version {
+        match self {
+            Self::TrailingWhitespace => Version::new(3, 0, 0),
+            Self::CheckAddedLargeFiles => Version::new(3, 4, 0),
+            Self::NameTestsTest => Version::new(4, 4, 0),
+            Self::CheckExecutablesHaveShebangs
+            | Self::CheckShebangScriptsAreExecutable
+            | Self::NoCommitToBranch
+            | Self::ForbidNewSubmodules
+            | Self::FixByteOrderMarker
+            | Self::RequirementsTxtFixer
+            | Self::FileContentsSorter
+            | Self::SortSimpleYaml
+            | Self::CheckAst
+            | Self::DebugStatements
+            | Self::CheckDocstringFirst => Version::new(4, 0, 0),
+        }
+    }
+
+    /// Whether this implementation is faithful to the `pre-commit-hooks` release.
+    fn supports(&self, version: &Version) -> bool {
+        *version >= self.min_rev() && *version <= LATEST_VERIFIED
+    }
+
+    /// Returns true if the builtin understands all the arguments passed to the hook.
+    fn accepts_args(&self, hook: &Hook) -> bool {
+        fn parses<T: Parser>(hook: &Hook) -> bool {
+            shlex::split(&hook.entry)
+                .is_some_and(|entry| T::try_parse_from(entry.iter().chain(&hook.args)).is_ok())
+        }
+
+        match self {
+            Self::TrailingWhitespace => parses::<fix_trailing_whitespace::Args>(hook),
+            Self::CheckAddedLargeFiles => parses::<check_added_large_files::Args>(hook),
+            Self::NoCommitToBranch => parses::<no_commit_to_branch::Args>(hook),
+            Self::FileContentsSorter => parses::<file_contents_sorter::Args>(hook),
+            Self::NameTestsTest => parses::<name_tests_test::Args>(hook),
+            // The remaining hooks don't take any arguments.
+            Self::CheckExecutablesHaveShebangs
+            | Self::CheckShebangScriptsAreExecutable
+            | Self::ForbidNewSubmodules
+            | Self::FixByteOrderMarker
+            | Self::RequirementsTxtFixer
+            | Self::SortSimpleYaml
+            | Self::CheckAst
+            | Self::DebugStatements
+            | Self::CheckDocstringFirst => hook.args.is_empty(),
+        }
+    }
+}
+
+/// Returns the builtin implementation of a `pre-commit-hooks` hook at the given revision.
+///
+/// Returns `None` if there is no builtin implementation, or if it may behave differently
+/// from the Python hook, in which case the Python hook should be used instead.
+pub(crate) fn find(hook: &Hook, rev: &str) -> Option<Implemented> {
+    let implemented = Implemented::from_str(hook.id.as_str()).ok()?;
+
+    let Some(version) = parse_rev(rev) else {
+        debug!(hook = %hook.id, rev, "Revision is not a version, using the Python hook");
+        return None;
+    };
+    if !implemented.supports(&version) {
+        debug!(hook = %hook.id, rev, "Revision is not supported by the builtin hook, using the Python hook");
+        return None;
+    }
+    // The builtin hooks run in-process, the Python hook sees the environment it sets.
+    if !hook.env.is_empty() {
+        debug!(hook = %hook.id, "Hook sets `env`, using the Python hook");
+        return None;
+    }
+    if !implemented.accepts_args(hook) {
+        debug!(hook = %hook.id, args = ?hook.args, "Arguments are not supported by the builtin hook, using the Python hook");
+        return None;
+    }
+
+    Some(implemented)
+}
+
+/// Parse a tag like `v5.0.0` or `v4.4`, or the commit of a release, into a version.
+fn parse_rev(rev: &str) -> Option<Version> {
+    if let Some((_, version)) = RELEASE_COMMITS.iter().find(|(sha, _)| *sha == rev) {
+        return Some(version.clone());
+    }
+    let rev = rev.strip_prefix('v').unwrap_or(rev);
+    let parts = rev
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match parts[..] {
+        [major] => Some(Version::new(major, 0, 0)),
+        [major, minor] => Some(Version::new(major, minor, 0)),
+        [major, minor, patch] => Some(Version::new(major, minor, patch)),
+        _ => None,
+    }
+}
+
+/// Returns true if the url is `pre-commit/pre-commit-hooks` or one of the configured mirrors.
+pub(crate) fn is_pre_commit_hooks(url: &Url, mirrors: &[Url]) -> bool {
+    /// Compare urls by host and path, ignoring a trailing `/` or `.git`.
+    fn normalize(url: &Url) -> (Option<String>, &str) {
+        let path = url.path().trim_end_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        (url.host_str().map(str::to_ascii_lowercase), path)
+    }
+
+    let url = normalize(url);
+    if url
+        == (
+            Some("github.com".to_string()),
+            "/pre-commit/pre-commit-hooks",
+        )
+    {
+        return true;
+    }
+    mirrors.iter().any(|mirror| normalize(mirror) == url)
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{Implemented, is_pre_commit_hooks, parse_rev};
+
+    #[test]
+    fn rev() {
+        assert_eq!(parse_rev("v5.0.0"), Some(semver::Version::new(5, 0, 0)));
+        assert_eq!(parse_rev("4.4"), Some(semver::Version::new(4, 4, 0)));
+        assert_eq!(
+            parse_rev("cef0300fd0fc4d2a87a85fa2093c6b283ea36f4b"),
+            Some(semver::Version::new(5, 0, 0))
+        );
+        assert_eq!(parse_rev("0123456789abcdef0123456789abcdef01234567"), None);
+        assert_eq!(parse_rev("main"), None);
+        assert_eq!(parse_rev("v5.0.0-rc1"), None);
+    }
+
+    #[test]
+    fn supported_revs() {
+        let hook = Implemented::TrailingWhitespace;
+        assert!(!hook.supports(&semver::Version::new(2, 5, 0)));
+        assert!(hook.supports(&semver::Version::new(5, 0, 0)));
+        assert!(!hook.supports(&semver::Version::new(5, 0, 1)));
+        assert!(!hook.supports(&semver::Version::new(6, 0, 0)));
+    }
+
+    #[test]
+    fn pre_commit_hooks_url() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        let mirrors = [url("https://gitlab.example.com/mirrors/pre-commit-hooks")];
+
+        assert!(is_pre_commit_hooks(
+            &url("https://github.com/pre-commit/pre-commit-hooks"),
+            &[]
+        ));
+        assert!(is_pre_commit_hooks(
+            &url("https://github.com/pre-commit/pre-commit-hooks.git"),
+            &[]
+        ));
+        assert!(!is_pre_commit_hooks(
+            &url("https://github.com/someone/pre-commit-hooks"),
+            &[]
+        ));
+        assert!(is_pre_commit_hooks(
+            &url("https://gitlab.example.com/mirrors/pre-commit-hooks.git/"),
+            &mirrors
+        ));
+        assert!(!is_pre_commit_hooks(
+            &url("https://gitlab.example.com/mirrors/pre-commit-hooks"),
+            &[]
+        ));
+    }
 }
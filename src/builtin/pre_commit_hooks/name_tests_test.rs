@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use clap::Parser;
+use fancy_regex::Regex;
+
+use crate::hook::Hook;
+
+const PYTEST: &str = r".*_test\.py";
+const PYTEST_TEST_FIRST: &str = r"test_.*\.py";
+const UNITTEST: &str = r"test.*\.py";
+
+#[derive(Parser)]
+pub(crate) struct Args {
+    /// (the default) ensure tests match `.*_test\.py`.
+    #[arg(long, group = "pattern")]
+    pytest: bool,
+    /// Ensure tests match `test_.*\.py`.
+    #[arg(long, group = "pattern")]
+    pytest_test_first: bool,
+    /// Ensure tests match `test.*\.py`.
+    #[arg(long, alias = "unittest", group = "pattern")]
+    django: bool,
+}
+
+impl Args {
+    fn pattern(&self) -> &'static str {
+        if self.pytest_test_first {
+            PYTEST_TEST_FIRST
+        } else if self.django {
+            UNITTEST
+        } else {
+            PYTEST
+        }
+    }
+}
+
+/// Checks that test files are named correctly.
+#[allow(clippy::unused_async)]
+pub(crate) async fn name_tests_test(
+    hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let entry = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
+    let args = Args::try_parse_from(entry.iter().chain(&hook.args))?;
+
+    let pattern = args.pattern();
+    let regex = Regex::new(&format!("^(?:{pattern})$"))?;
+
+    let mut code = 0;
+    let mut output = String::new();
+
+    for filename in filenames {
+        let base = Path::new(filename)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(filename);
+        if regex.is_match(base)? || base == "__init__.py" || base == "conftest.py" {
+            continue;
+        }
+
+        code = 1;
+        writeln!(output, "{filename} does not match pattern \"{pattern}\"")?;
+    }
+
+    Ok((code, output.into_bytes()))
+}
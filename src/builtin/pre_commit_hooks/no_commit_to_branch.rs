@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use clap::Parser;
+use fancy_regex::Regex;
+
+use crate::git::get_current_branch;
+use crate::hook::Hook;
+
+#[derive(Parser)]
+pub(crate) struct Args {
+    /// Branch to disallow commits to, may be specified multiple times.
+    #[arg(short, long)]
+    branch: Vec<String>,
+    /// Regex pattern for branch name to disallow commits to, may be specified multiple times.
+    #[arg(short, long)]
+    pattern: Vec<String>,
+}
+
+pub(crate) async fn no_commit_to_branch(
+    hook: &Hook,
+    _filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let entry = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
+    let args = Args::try_parse_from(entry.iter().chain(&hook.args))?;
+
+    let protected = if args.branch.is_empty() {
+        vec!["master".to_string(), "main".to_string()]
+    } else {
+        args.branch
+    };
+    // Patterns only need to match at the start of the branch name, like Python's `re.match`.
+    let patterns = args
+        .pattern
+        .iter()
+        .map(|pattern| Regex::new(&format!("^(?:{pattern})")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let Some(branch) = get_current_branch().await? else {
+        return Ok((0, Vec::new()));
+    };
+
+    if protected.contains(&branch)
+        || patterns
+            .iter()
+            .any(|re| re.is_match(&branch).unwrap_or(false))
+    {
+        Ok((1, Vec::new()))
+    } else {
+        Ok((0, Vec::new()))
+    }
+}
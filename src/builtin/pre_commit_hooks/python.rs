@@ -0,0 +1,91 @@
+//! Helpers for hooks that inspect Python source code.
+
+use rustpython_parser::text_size::TextSize;
+use rustpython_parser::{Parse, ast};
+
+/// A syntax error found while parsing Python source.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct SyntaxError {
+    /// 1-based line number.
+    pub(crate) line: usize,
+    /// 1-based column number, in bytes.
+    pub(crate) col: usize,
+    pub(crate) message: String,
+}
+
+/// Parse Python source code into statements, like `ast.parse`.
+pub(crate) fn parse(source: &[u8]) -> Result<ast::Suite, SyntaxError> {
+    let source = decode(source)?;
+    ast::Suite::parse(source, "<unknown>").map_err(|err| {
+        let (line, col) = location(source, err.offset);
+        SyntaxError {
+            line,
+            col: col + 1,
+            message: err.error.to_string(),
+        }
+    })
+}
+
+/// Decode UTF-8 source, skipping the byte order mark Python allows.
+pub(crate) fn decode(source: &[u8]) -> Result<&str, SyntaxError> {
+    let source = source.strip_prefix(b"\xef\xbb\xbf").unwrap_or(source);
+    std::str::from_utf8(source).map_err(|err| {
+        // The part before the error is valid.
+        let valid = std::str::from_utf8(&source[..err.valid_up_to()]).unwrap_or_default();
+        let (line, col) = line_col(valid);
+        SyntaxError {
+            line,
+            col: col + 1,
+            message: "invalid utf-8 in source".to_string(),
+        }
+    })
+}
+
+/// The 1-based line and 0-based byte column of an offset, like Python's `lineno` and
+/// `col_offset`.
+pub(crate) fn location(source: &str, offset: TextSize) -> (usize, usize) {
+    line_col(&source[..usize::from(offset).min(source.len())])
+}
+
+/// The line and column of the end of `before`.
+fn line_col(before: &str) -> (usize, usize) {
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before.len() - line_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    fn error(source: &str) -> Option<(usize, usize, String)> {
+        parse(source.as_bytes())
+            .err()
+            .map(|err| (err.line, err.col, err.message))
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(error(""), None);
+        assert_eq!(error("\u{feff}import os\n"), None);
+        assert_eq!(error("import os\n\ndef f(x):\n    return x\n"), None);
+        assert_eq!(error("x = f'{1 + 2}'\n"), None);
+        assert_eq!(error("match x:\n    case 1:\n        pass\n"), None);
+        assert!(error("def f(:\n    pass\n").is_some_and(|(line, ..)| line == 1));
+        assert!(error("x = 1\nif True\n    pass\n").is_some_and(|(line, ..)| line == 2));
+        assert!(error("print 'hello'\n").is_some_and(|(line, ..)| line == 1));
+        assert_eq!(
+            error("def f(a=1, b): pass\n"),
+            Some((
+                1,
+                12,
+                "non-default argument follows default argument".to_string()
+            ))
+        );
+        assert_eq!(
+            parse(b"x = 1\ny = '\xff'\n")
+                .err()
+                .map(|err| (err.line, err.col)),
+            Some((2, 6))
+        );
+    }
+}
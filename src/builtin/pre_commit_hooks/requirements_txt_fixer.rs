@@ -0,0 +1,239 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use anyhow::Result;
+use bstr::ByteSlice;
+use futures::StreamExt;
+
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+pub(crate) async fn requirements_txt_fixer(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let content = fs_err::tokio::read(filename).await?;
+
+            if let Some(fixed) = fix_requirements(&content) {
+                fs_err::tokio::write(filename, &fixed).await?;
+                anyhow::Ok((1, format!("Fixing {filename}\n").into_bytes()))
+            } else {
+                anyhow::Ok((0, Vec::new()))
+            }
+        })
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let (c, o) = result?;
+        code |= c;
+        output.extend(o);
+    }
+
+    Ok((code, output))
+}
+
+/// A requirement line (possibly continued with `\`) and the comments above it.
+#[derive(Default)]
+struct Requirement {
+    value: Option<Vec<u8>>,
+    comments: Vec<Vec<u8>>,
+}
+
+impl Requirement {
+    /// A requirement with this value holds the top of file comment.
+    const TOP_OF_FILE: &[u8] = b"\n";
+
+    /// The package name used as the sort key.
+    fn name(&self) -> Vec<u8> {
+        let value = self.value.as_deref().unwrap_or_default();
+        let name = value.to_ascii_lowercase();
+
+        for egg in ["#egg=", "&egg="] {
+            if let Some(pos) = name.find(egg) {
+                return name[pos + egg.len()..].to_vec();
+            }
+        }
+
+        // Everything until the environment marker separator or whitespace.
+        let end = name
+            .iter()
+            .position(|&b| b == b';' || b.is_ascii_whitespace())
+            .unwrap_or(name.len());
+        let name = &name[..end];
+
+        // Everything until the first version comparison operator.
+        let end = name
+            .iter()
+            .enumerate()
+            .position(|(i, &b)| match b {
+                b'<' | b'>' => true,
+                b'=' | b'!' | b'~' => name.get(i + 1) == Some(&b'='),
+                _ => false,
+            })
+            .unwrap_or(name.len());
+        name[..end].to_vec()
+    }
+
+    fn is_complete(&self) -> bool {
+        self.value.as_ref().is_some_and(|value| {
+            !value
+                .trim_end_with(|c| c == '\r' || c == '\n')
+                .ends_with(b"\\")
+        })
+    }
+
+    fn append_value(&mut self, line: &[u8]) {
+        self.value.get_or_insert_default().extend_from_slice(line);
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The top of file comment always comes first.
+        match (self.value.as_deref(), other.value.as_deref()) {
+            (Some(Self::TOP_OF_FILE), _) => Ordering::Less,
+            (_, Some(Self::TOP_OF_FILE)) => Ordering::Greater,
+            // With the same name, the one with comments goes first, so the duplicate
+            // removed after sorting is the one without comments.
+            _ => self
+                .name()
+                .cmp(&other.name())
+                .then_with(|| self.comments.is_empty().cmp(&other.comments.is_empty())),
+        }
+    }
+}
+
+/// Sort the requirements and remove duplicates, returns `None` if nothing changed.
+fn fix_requirements(content: &[u8]) -> Option<Vec<u8>> {
+    // If the file is empty (i.e. only whitespace/newlines) exit early.
+    if content.trim_ascii().is_empty() {
+        return None;
+    }
+
+    let mut requirements: Vec<Requirement> = Vec::new();
+
+    let mut lines: Vec<Vec<u8>> = content
+        .split_inclusive(|&b| b == b'\n')
+        .map(<[u8]>::to_vec)
+        .collect();
+    // Add a new line in case one is missing.
+    if let Some(last) = lines.last_mut() {
+        if !last.ends_with(b"\n") {
+            last.push(b'\n');
+        }
+    }
+
+    for line in lines {
+        // If the most recent requirement has a value, start building the next one.
+        if requirements.last().is_none_or(Requirement::is_complete) {
+            requirements.push(Requirement::default());
+        }
+        let is_first = requirements.len() == 1;
+        let requirement = requirements.last_mut().expect("requirements is not empty");
+
+        // A blank line before any requirements ends the top of file comment.
+        if is_first && line.trim_ascii().is_empty() {
+            if requirement
+                .comments
+                .first()
+                .is_some_and(|comment| comment.starts_with(b"#"))
+            {
+                requirement.value = Some(Requirement::TOP_OF_FILE.to_vec());
+            } else {
+                requirement.comments.push(line);
+            }
+        } else if line.trim_ascii_start().starts_with(b"#") || line.trim_ascii().is_empty() {
+            requirement.comments.push(line);
+        } else {
+            requirement.append_value(&line);
+        }
+    }
+
+    // If the file ends with a comment, preserve it at the end.
+    let rest = if requirements
+        .last()
+        .is_some_and(|requirement| requirement.value.is_none())
+    {
+        requirements.pop().map(|r| r.comments).unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    // Remove `pkg-resources==0.0.0`, which is added by the broken pip package under Debian.
+    requirements.retain(|requirement| {
+        !matches!(
+            requirement.value.as_deref(),
+            Some(b"pkg-resources==0.0.0\n" | b"pkg_resources==0.0.0\n")
+        )
+    });
+
+    // Sort the requirements and remove duplicates.
+    requirements.sort_by(Requirement::cmp);
+
+    let mut after = Vec::with_capacity(content.len() + 1);
+    let mut prev: Option<&[u8]> = None;
+    for requirement in &requirements {
+        for comment in &requirement.comments {
+            after.extend_from_slice(comment);
+        }
+        let value = requirement.value.as_deref().unwrap_or_default();
+        if prev != Some(value) {
+            after.extend_from_slice(value);
+            prev = Some(value);
+        }
+    }
+    for comment in rest {
+        after.extend_from_slice(&comment);
+    }
+
+    if after == content { None } else { Some(after) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fix_requirements;
+
+    fn fix(content: &str) -> Option<String> {
+        fix_requirements(content.as_bytes()).map(|s| String::from_utf8(s).unwrap())
+    }
+
+    #[test]
+    fn requirements() {
+        assert_eq!(fix(""), None);
+        assert_eq!(fix("\n"), None);
+        assert_eq!(fix("foo\nbar\n").as_deref(), Some("bar\nfoo\n"));
+        assert_eq!(fix("bar\nfoo\n"), None);
+        assert_eq!(fix("foo\nbar").as_deref(), Some("bar\nfoo\n"));
+        assert_eq!(fix("bar\nfoo"), Some("bar\nfoo\n".to_string()));
+        assert_eq!(
+            fix("# comment\n\nfoo\n# bar comment\nbar\n").as_deref(),
+            Some("# comment\n\n# bar comment\nbar\nfoo\n")
+        );
+        assert_eq!(fix("foo\n# comment at end\n").as_deref(), None,);
+        assert_eq!(
+            fix("b==1.0.0\nc=2.0.0 \\\n ; python_version < '3'\na\n").as_deref(),
+            Some("a\nb==1.0.0\nc=2.0.0 \\\n ; python_version < '3'\n")
+        );
+        assert_eq!(
+            fix("Django>=1.10\ndjango-debug-toolbar<2\n").as_deref(),
+            None
+        );
+        assert_eq!(
+            fix("git+ssh://git_url@tag#egg=ocflib\nDjango\nijk\n").as_deref(),
+            Some("Django\nijk\ngit+ssh://git_url@tag#egg=ocflib\n")
+        );
+        assert_eq!(fix("a==1\nb==1\na==1\n").as_deref(), Some("a==1\nb==1\n"));
+        assert_eq!(
+            fix("a==1\n# comment\na==1\n").as_deref(),
+            Some("# comment\na==1\n")
+        );
+        assert_eq!(
+            fix("bar\npkg-resources==0.0.0\nfoo\n").as_deref(),
+            Some("bar\nfoo\n")
+        );
+    }
+}
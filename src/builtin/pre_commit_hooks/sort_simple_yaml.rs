@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures::StreamExt;
+
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+pub(crate) async fn sort_simple_yaml(
+    _hook: &Hook,
+    filenames: &[&String],
+    _env_vars: &HashMap<&'static str, String>,
+) -> Result<(i32, Vec<u8>)> {
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let content = fs_err::tokio::read_to_string(filename).await?;
+            let lines: Vec<&str> = content.lines().map(str::trim_end).collect();
+
+            let sorted = sort(&lines);
+            if sorted == lines {
+                return anyhow::Ok((0, Vec::new()));
+            }
+
+            let mut new_content = sorted.join("\n");
+            new_content.push('\n');
+            fs_err::tokio::write(filename, new_content).await?;
+
+            anyhow::Ok((1, format!("Fixing {filename}\n").into_bytes()))
+        })
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let (c, o) = result?;
+        code |= c;
+        output.extend(o);
+    }
+
+    Ok((code, output))
+}
+
+/// Sort a YAML file in alphabetical order, keeping blocks together.
+///
+/// The leading comment lines are kept as the header,
+/// the remaining blocks are separated by empty lines and sorted by their first key.
+fn sort<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let header_len = lines
+        .iter()
+        .position(|line| line.is_empty() || !line.starts_with('#'))
+        .unwrap_or(lines.len());
+    let (header, rest) = lines.split_at(header_len);
+
+    let mut blocks: Vec<&[&str]> = rest
+        .split(|line| line.is_empty())
+        .filter(|block| !block.is_empty())
+        .collect();
+    blocks.sort_by_key(|block| first_key(block));
+
+    let mut new_lines = header.to_vec();
+    for block in blocks {
+        if !new_lines.is_empty() {
+            new_lines.push("");
+        }
+        new_lines.extend_from_slice(block);
+    }
+    new_lines
+}
+
+/// Returns the first non-comment line of a block, without a leading quote.
+fn first_key<'a>(block: &[&'a str]) -> &'a str {
+    block
+        .iter()
+        .find(|line| !line.starts_with('#'))
+        .map(|line| line.strip_prefix(['\'', '"']).unwrap_or(line))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sort;
+
+    fn sorted(content: &str) -> String {
+        let lines: Vec<&str> = content.lines().collect();
+        sort(&lines).join("\n")
+    }
+
+    #[test]
+    fn sort_blocks() {
+        assert_eq!(sorted(""), "");
+        assert_eq!(sorted("b: 1\n\na: 2"), "a: 2\n\nb: 1");
+        assert_eq!(
+            sorted("# header\n# more\n\nb: 1\n\n\n# a comment\na:\n  c: 2"),
+            "# header\n# more\n\n# a comment\na:\n  c: 2\n\nb: 1"
+        );
+        assert_eq!(
+            sorted("'b': 1\n\n\"a\": 2\n\nc: 3"),
+            "\"a\": 2\n\n'b': 1\n\nc: 3"
+        );
+        // Leading comments are always treated as the header.
+        assert_eq!(
+            sorted("# comment\nb: 1\n\na: 2"),
+            "# comment\n\na: 2\n\nb: 1"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::cli::ExitStatus;
+use crate::cli::cache::format_size;
+use crate::cli::reporter::{HookInitReporter, HookInstallReporter};
+use crate::cli::run::install_hooks;
+use crate::config::{self, Language};
+use crate::fs::Simplified;
+use crate::hook::Project;
+use crate::languages::UvInstaller;
+use crate::printer::Printer;
+use crate::store::Store;
+use crate::warn_user;
+
+/// The file in a bundle listing its entries.
+const MANIFEST_FILE: &str = "bundle.json";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    /// The store the bundle was exported from, hook environments refer to it by absolute paths.
+    store: PathBuf,
+    /// The entries relative to the store, e.g. `repos/<digest>` or `tools/python/<version>`.
+    entries: Vec<String>,
+}
+
+pub(crate) async fn bundle_export(
+    config: Option<PathBuf>,
+    file: PathBuf,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    let reporter = HookInitReporter::from(printer);
+    let hooks = project.init_hooks(&store, Some(&reporter)).await?;
+    project.warn_unlocked(&hooks);
+    let reporter = HookInstallReporter::from(printer);
+    install_hooks(&hooks, &reporter).await?;
+
+    let mut paths = BTreeSet::new();
+    // A moved tag or branch is cloned at its new commit, next to the clone recording the move.
+    for repo in &project.config().repos {
+        if let config::Repo::Remote(repo) = repo {
+            let path = store.repo_path(repo);
+            if path.is_dir() {
+                paths.insert(path);
+            }
+        }
+    }
+    for hook in &hooks {
+        paths.extend(hook.repo_path().map(Path::to_path_buf));
+        if let Some(env) = hook.env_path() {
+            paths.insert(env.to_path_buf());
+            paths.extend(hook.language.toolchain(hook).await?);
+        }
+    }
+    // The managed uv, to check and reinstall Python environments without downloading it.
+    let uv = UvInstaller::managed_path(&store);
+    if uv.is_file() && hooks.iter().any(|hook| hook.language == Language::Python) {
+        paths.extend(uv.parent().map(Path::to_path_buf));
+    }
+
+    let mut entries = Vec::new();
+    let mut manifest = Manifest {
+        store: store.path().to_path_buf(),
+        entries: Vec::new(),
+    };
+    for path in paths {
+        // Local repos are not in the store.
+        let Ok(relative) = path.strip_prefix(store.path()) else {
+            continue;
+        };
+        manifest
+            .entries
+            .push(relative.to_string_lossy().replace('\\', "/"));
+        entries.push((relative.to_path_buf(), path));
+    }
+
+    let temp = tempfile::tempdir()?;
+    let manifest_path = temp.path().join(MANIFEST_FILE);
+    fs_err::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?)?;
+    entries.push((PathBuf::from(MANIFEST_FILE), manifest_path));
+
+    let writer = fs_err::tokio::File::create(&file).await?;
+    archive::tar_gz(writer, &entries).await?;
+
+    writeln!(
+        printer.stdout(),
+        "Bundled {} entries into `{}` ({})",
+        manifest.entries.len(),
+        file.user_display().cyan(),
+        format_size(fs_err::metadata(&file)?.len())
+    )?;
+
+    Ok(ExitStatus::Success)
+}
+
+pub(crate) async fn bundle_import(file: PathBuf, printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?.init()?;
+
+    let temp = tempfile::tempdir_in(store.path())?;
+    let reader = fs_err::tokio::File::open(&file).await?;
+    archive::untar_gz(reader, temp.path())
+        .await
+        .with_context(|| format!("Failed to unpack `{}`", file.user_display()))?;
+    let manifest: Manifest = fs_err::read(temp.path().join(MANIFEST_FILE))
+        .map_err(anyhow::Error::from)
+        .and_then(|manifest| Ok(serde_json::from_slice(&manifest)?))
+        .with_context(|| format!("`{}` is not a prefligit bundle", file.user_display()))?;
+
+    let has_envs = manifest
+        .entries
+        .iter()
+        .any(|entry| !entry.starts_with("repos/"));
+    if has_envs && manifest.store != store.path() {
+        warn_user!(
+            "The bundle was exported from the store at `{}`, its hook environments may not work at `{}`. Set `PREFLIGIT_HOME` to `{}` to use them as they are.",
+            manifest.store.simplified_display(),
+            store.path().simplified_display(),
+            manifest.store.simplified_display(),
+        );
+    }
+
+    let mut imported = 0;
+    let mut skipped = 0;
+    for entry in &manifest.entries {
+        let relative = Path::new(entry);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            anyhow::bail!("Invalid entry `{entry}` in the bundle");
+        }
+
+        let target = store.path().join(relative);
+        if let Some(parent) = target.parent() {
+            fs_err::tokio::create_dir_all(parent).await?;
+        }
+        // Toolchains are installed under their own locks, by uv or the node installer.
+        let _lock = if entry.starts_with("tools/") {
+            None
+        } else {
+            Some(Store::lock_entry(&target, format!("store entry `{entry}`")).await?)
+        };
+        if target.try_exists()? {
+            skipped += 1;
+            continue;
+        }
+        fs_err::tokio::rename(temp.path().join(relative), &target).await?;
+        imported += 1;
+    }
+
+    writeln!(
+        printer.stdout(),
+        "Imported {imported} entries into `{}`, {skipped} already present",
+        store.path().user_display().cyan()
+    )?;
+
+    Ok(ExitStatus::Success)
+}
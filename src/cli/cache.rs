@@ -0,0 +1,289 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::cli::stash::format_age;
+use crate::fs::{LockedFile, Simplified};
+use crate::printer::Printer;
+use crate::store::{LAST_USED_FILE, Store, ToolBucket};
+
+/// A cloned repo or a hook environment in the store.
+struct CacheEntry {
+    path: PathBuf,
+    /// The repo it comes from, as `<url>@<rev>` or `local`.
+    source: Option<String>,
+    last_used: Option<SystemTime>,
+    size: u64,
+}
+
+impl CacheEntry {
+    fn new(path: PathBuf, marker: &str) -> Self {
+        let source = fs_err::read_to_string(path.join(".repo_source"))
+            .ok()
+            .map(|source| source.trim().to_string());
+        // Entries not used since `.last_used` was introduced fall back to when they were created.
+        let last_used = [LAST_USED_FILE, marker]
+            .into_iter()
+            .find_map(|file| path.join(file).metadata().and_then(|m| m.modified()).ok());
+        let size = dir_size(&path);
+        Self {
+            path,
+            source,
+            last_used,
+            size,
+        }
+    }
+
+    /// Whether the entry comes from the repo, with any rev.
+    fn is_from(&self, repo: &str) -> bool {
+        self.source.as_deref().is_some_and(|source| {
+            source == repo
+                || source
+                    .strip_prefix(repo)
+                    .is_some_and(|rev| rev.starts_with('@'))
+        })
+    }
+}
+
+/// The entries in a bucket of the store, largest first.
+fn entries(dir: &Path, marker: &str) -> Vec<CacheEntry> {
+    let mut entries: Vec<_> = fs_err::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .map(|path| CacheEntry::new(path, marker))
+        .collect();
+    entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    entries
+}
+
+fn repos(store: &Store) -> Vec<CacheEntry> {
+    entries(&store.repos_dir(), ".cloned_ok")
+}
+
+fn hooks(store: &Store) -> Vec<CacheEntry> {
+    entries(&store.hooks_dir(), ".installed_ok")
+}
+
+/// The disk usage of a directory, without following symlinks.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs_err::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Parse an age like `30d`, `12h`, `2w`.
+pub(crate) fn parse_age(age: &str) -> Result<Duration, String> {
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (value, unit) = age.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid age `{age}`, expected e.g. `30d`"))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid unit in `{age}`, expected one of `s`, `m`, `h`, `d`, `w`"
+            ));
+        }
+    };
+    Ok(Duration::from_secs(value * secs))
+}
+
+pub(crate) fn cache_dir(printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+    writeln!(printer.stdout(), "{}", store.path().simplified_display())?;
+    Ok(ExitStatus::Success)
+}
+
+pub(crate) fn cache_info(printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+
+    if !store.path().exists() {
+        writeln!(
+            printer.stdout(),
+            "The store `{}` is empty",
+            store.path().user_display().cyan()
+        )?;
+        return Ok(ExitStatus::Success);
+    }
+
+    writeln!(
+        printer.stdout(),
+        "{} `{}` ({})",
+        "Store".bold(),
+        store.path().user_display().cyan(),
+        format_size(dir_size(store.path()))
+    )?;
+    let buckets = [
+        ("repos", store.repos_dir()),
+        ("hooks", store.hooks_dir()),
+        ("patches", store.patches_dir()),
+        ("tools/uv", store.tools_path(ToolBucket::Uv)),
+        ("tools/python", store.tools_path(ToolBucket::Python)),
+        ("tools/node", store.tools_path(ToolBucket::Node)),
+    ];
+    for (name, path) in buckets {
+        writeln!(
+            printer.stdout(),
+            "  {}: {}",
+            name.dimmed(),
+            format_size(dir_size(&path))
+        )?;
+    }
+
+    for (title, entries) in [("Repos", repos(&store)), ("Hooks", hooks(&store))] {
+        if entries.is_empty() {
+            continue;
+        }
+        writeln!(printer.stdout(), "\n{}", title.bold())?;
+        for entry in entries {
+            writeln!(
+                printer.stdout(),
+                "  {}  {}  {}",
+                entry.source.as_deref().unwrap_or("unknown repo"),
+                format_size(entry.size),
+                entry
+                    .last_used
+                    .map_or_else(|| "never used".to_string(), format_age)
+                    .dimmed()
+            )?;
+            writeln!(
+                printer.stdout(),
+                "    {}",
+                entry.path.user_display().dimmed()
+            )?;
+        }
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+pub(crate) async fn cache_prune(
+    older_than: Option<Duration>,
+    repo: Option<String>,
+    dry_run: bool,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+
+    if !store.path().exists() {
+        writeln!(printer.stdout(), "Nothing to prune")?;
+        return Ok(ExitStatus::Success);
+    }
+
+    let _lock = store.lock_async().await?;
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    let mut freed = 0;
+    for entry in repos(&store).into_iter().chain(hooks(&store)) {
+        if let Some(older_than) = older_than {
+            let age = entry
+                .last_used
+                .map(|last_used| now.duration_since(last_used).unwrap_or_default());
+            if age.is_some_and(|age| age < older_than) {
+                continue;
+            }
+        }
+        if let Some(repo) = &repo {
+            if !entry.is_from(repo) {
+                continue;
+            }
+        }
+
+        // Skip the entries being cloned or installed.
+        let Some(_entry_lock) =
+            LockedFile::try_acquire(entry.path.with_extension("lock"), "store entry")?
+        else {
+            writeln!(
+                printer.stdout(),
+                "Skipped `{}`, it is in use",
+                entry.path.user_display().cyan()
+            )?;
+            continue;
+        };
+        if !dry_run {
+            fs_err::remove_dir_all(&entry.path)?;
+        }
+        writeln!(
+            printer.stdout(),
+            "{} `{}` ({}, {})",
+            if dry_run { "Would remove" } else { "Removed" },
+            entry.path.user_display().cyan(),
+            entry.source.as_deref().unwrap_or("unknown repo"),
+            format_size(entry.size)
+        )?;
+        removed += 1;
+        freed += entry.size;
+    }
+
+    if removed == 0 {
+        writeln!(printer.stdout(), "Nothing to prune")?;
+    } else {
+        writeln!(
+            printer.stdout(),
+            "{} {removed} entries, {}",
+            if dry_run { "Would remove" } else { "Removed" },
+            format_size(freed).bold()
+        )?;
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_size, parse_age};
+
+    #[test]
+    fn age() {
+        assert_eq!(parse_age("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 86400)));
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
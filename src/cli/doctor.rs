@@ -0,0 +1,317 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::cli::stash::saved_patches;
+use crate::fs::{LockedFile, Simplified};
+use crate::git::GIT;
+use crate::hook::Project;
+use crate::languages::{UV_MIN_VERSION, UvInstaller};
+use crate::printer::Printer;
+use crate::process::Cmd;
+use crate::store::Store;
+
+enum Level {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// The outcome of a check, with a hint to fix it.
+struct Check {
+    level: Level,
+    message: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Ok,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warning(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warning,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn error(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            level: Level::Error,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Run `<program> <args>` and return its trimmed stdout.
+async fn version_output(program: &str, args: &[&str]) -> Result<String> {
+    let output = Cmd::new(program, format!("get {program} version"))
+        .args(args)
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn check_git() -> Check {
+    let git = match GIT.as_ref() {
+        Ok(git) => git,
+        Err(err) => {
+            return Check::error(
+                format!("git is not found: {err}"),
+                "install git and make sure it is in `PATH`",
+            );
+        }
+    };
+    match version_output(&git.to_string_lossy(), &["--version"]).await {
+        Ok(version) => Check::ok(format!("{version} at `{}`", git.user_display())),
+        Err(err) => Check::error(
+            format!("git at `{}` does not work: {err}", git.user_display()),
+            "reinstall git",
+        ),
+    }
+}
+
+async fn check_container_runtime() -> Check {
+    if which::which("docker").is_ok() {
+        return match version_output("docker", &["version", "--format", "{{.Server.Version}}"]).await
+        {
+            Ok(version) => Check::ok(format!("docker {version} is reachable")),
+            Err(_) => Check::warning(
+                "docker is installed but its daemon is not reachable",
+                "start the docker daemon, or set `DOCKER_HOST`, to run `docker` and `docker_image` hooks",
+            ),
+        };
+    }
+    if which::which("podman").is_ok() {
+        return match version_output("podman", &["version", "--format", "{{.Version}}"]).await {
+            Ok(version) => Check::warning(
+                format!("podman {version} is installed, but hooks run `docker`"),
+                "install `podman-docker`, or link `docker` to `podman`, to run `docker` and `docker_image` hooks",
+            ),
+            Err(_) => Check::warning(
+                "podman is installed but does not work",
+                "run `podman info` to see what is wrong",
+            ),
+        };
+    }
+    Check::warning(
+        "neither docker nor podman is found",
+        "install docker to run `docker` and `docker_image` hooks",
+    )
+}
+
+async fn check_uv(store: &Store) -> Check {
+    if let Ok(uv) = which::which("uv") {
+        return match UvInstaller::version(&uv).await {
+            Ok(version) if version >= UV_MIN_VERSION => {
+                Check::ok(format!("uv {version} at `{}`", uv.user_display()))
+            }
+            Ok(version) => Check::warning(
+                format!(
+                    "uv {version} at `{}` is older than {UV_MIN_VERSION}, prefligit installs its own uv instead",
+                    uv.user_display()
+                ),
+                "upgrade uv with `uv self update`",
+            ),
+            Err(err) => Check::warning(
+                format!("uv at `{}` does not work: {err}", uv.user_display()),
+                "reinstall uv, prefligit installs its own uv meanwhile",
+            ),
+        };
+    }
+
+    let uv = UvInstaller::managed_path(store);
+    if !uv.is_file() {
+        return Check::ok("uv is not installed, prefligit installs it when needed");
+    }
+    match UvInstaller::version(&uv).await {
+        Ok(version) => Check::ok(format!("uv {version} at `{}`", uv.user_display())),
+        Err(err) => Check::error(
+            format!("uv at `{}` does not work: {err}", uv.user_display()),
+            format!("remove `{}` to reinstall it", uv.user_display()),
+        ),
+    }
+}
+
+/// Check the installed environments of the hooks in the config.
+///
+/// Only the repos cloned already are checked, without cloning the others or checking the
+/// clones against their remotes.
+async fn check_hooks(project: &Project, store: &Store, checks: &mut Vec<Check>) {
+    let (hooks, not_cloned) = match project.init_cloned_hooks(store) {
+        Ok(hooks) => hooks,
+        Err(err) => {
+            checks.push(Check::error(
+                format!("Failed to load hooks: {:#}", anyhow::Error::from(err)),
+                "check the `repo` and `rev` of the repos in the config",
+            ));
+            return;
+        }
+    };
+    if !not_cloned.is_empty() {
+        checks.push(Check::ok(format!(
+            "Repos {} are not cloned yet, their hooks are not checked",
+            not_cloned.iter().map(|repo| format!("`{repo}`")).join(", ")
+        )));
+    }
+
+    let mut seen = HashSet::new();
+    let mut installed = 0;
+    let mut missing = 0;
+    let mut busy = Vec::new();
+    for hook in &hooks {
+        let Some(env) = hook.env_path() else {
+            continue;
+        };
+        if !seen.insert(env.to_path_buf()) {
+            continue;
+        }
+        if !hook.installed() {
+            missing += 1;
+            continue;
+        }
+        // Hold the lock of the environment while checking it, as installing does.
+        let _lock = match LockedFile::try_acquire(env.with_extension("lock"), "store entry") {
+            Ok(Some(lock)) => lock,
+            Ok(None) => {
+                busy.push(hook.id.as_str());
+                continue;
+            }
+            Err(err) => {
+                checks.push(Check::error(
+                    format!(
+                        "Failed to lock the environment of hook `{}` at `{}`: {err}",
+                        hook.id,
+                        env.user_display()
+                    ),
+                    "check the permissions of the store",
+                ));
+                continue;
+            }
+        };
+        installed += 1;
+        if let Err(err) = hook.language.check_health(hook).await {
+            checks.push(Check::error(
+                format!(
+                    "The environment of hook `{}` at `{}` is broken: {err:#}",
+                    hook.id,
+                    env.user_display()
+                ),
+                format!(
+                    "remove `{}` to reinstall it on the next run",
+                    env.user_display()
+                ),
+            ));
+        }
+    }
+    checks.push(Check::ok(format!(
+        "{installed} hook environments installed, {missing} not installed yet"
+    )));
+    if !busy.is_empty() {
+        checks.push(Check::warning(
+            format!(
+                "The environments of hooks {} are locked by another prefligit process, they are not checked",
+                busy.iter().map(|id| format!("`{id}`")).join(", ")
+            ),
+            "wait for the other process to finish installing them, and run `prefligit doctor` again",
+        ));
+    }
+
+    // Environments without a marker were left by failed or interrupted installs,
+    // unless another process is installing them right now.
+    let incomplete: Vec<PathBuf> = fs_err::read_dir(store.hooks_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir() && !path.join(".installed_ok").exists())
+        .filter(|path| {
+            LockedFile::try_acquire(path.with_extension("lock"), "store entry")
+                .is_ok_and(|lock| lock.is_some())
+        })
+        .collect();
+    if !incomplete.is_empty() {
+        checks.push(Check::warning(
+            format!(
+                "{} hook environments were not installed completely",
+                incomplete.len()
+            ),
+            format!(
+                "remove them from `{}`, or run `prefligit clean` to remove all the environments",
+                store.hooks_dir().user_display()
+            ),
+        ));
+    }
+}
+
+pub(crate) async fn doctor(config: Option<PathBuf>, printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?.init()?;
+
+    let mut checks = vec![
+        check_git().await,
+        check_container_runtime().await,
+        check_uv(&store).await,
+    ];
+
+    let project = match Project::from_config_file(config) {
+        Ok(project) => {
+            checks.push(Check::ok(format!(
+                "Config `{}` is valid",
+                project.config_file().user_display()
+            )));
+            Some(project)
+        }
+        Err(err) => {
+            checks.push(Check::error(
+                format!("{:#}", anyhow::Error::from(err)),
+                "fix the config, or run `prefligit sample-config` to start a new one",
+            ));
+            None
+        }
+    };
+
+    if let Some(project) = project {
+        check_hooks(&project, &store, &mut checks).await;
+    }
+
+    let patches = saved_patches(&store)?;
+    if !patches.is_empty() {
+        checks.push(Check::warning(
+            format!(
+                "{} patches of non-staged changes could not be restored",
+                patches.len()
+            ),
+            "run `prefligit stash list` to see them, and `prefligit stash restore <id>` to apply them",
+        ));
+    }
+
+    let mut status = ExitStatus::Success;
+    for check in checks {
+        let level = match check.level {
+            Level::Ok => "ok".green().bold().to_string(),
+            Level::Warning => "warning".yellow().bold().to_string(),
+            Level::Error => {
+                status = ExitStatus::Failure;
+                "error".red().bold().to_string()
+            }
+        };
+        writeln!(printer.stdout(), "{level}: {}", check.message)?;
+        if let Some(hint) = check.hint {
+            writeln!(printer.stdout(), "  {}: {hint}", "hint".cyan().bold())?;
+        }
+    }
+
+    Ok(status)
+}
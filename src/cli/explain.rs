@@ -0,0 +1,80 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::cli::reporter::HookInitReporter;
+use crate::cli::run::{FileFilter, FilenameFilter};
+use crate::fs::{Simplified, normalize_path};
+use crate::hook::Project;
+use crate::identify::tags_from_path;
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Explain which hooks would run on a file, and which rules rejected it for the others.
+pub(crate) async fn explain(
+    config: Option<PathBuf>,
+    file: PathBuf,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    if std::fs::symlink_metadata(&file).is_err() {
+        writeln!(
+            printer.stderr(),
+            "File `{}` does not exist",
+            file.user_display().cyan()
+        )?;
+        return Ok(ExitStatus::Failure);
+    }
+
+    let mut filename = file.to_string_lossy().to_string();
+    normalize_path(&mut filename);
+    let mut tags = tags_from_path(&file)?;
+    tags.sort_unstable();
+
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    let reporter = HookInitReporter::from(printer);
+    let hooks = project.init_hooks(&store, Some(&reporter)).await?;
+
+    writeln!(printer.stdout(), "{}", filename.bold())?;
+    writeln!(
+        printer.stdout(),
+        "  {}: {}",
+        "tags".dimmed(),
+        tags.join(", ")
+    )?;
+
+    let global = FilenameFilter::new(
+        project.config().files.as_deref(),
+        project.config().exclude.as_deref(),
+    )?;
+    let rejections = global.rejections(&filename);
+    if !rejections.is_empty() {
+        writeln!(
+            printer.stdout(),
+            "No hook runs on it, it is {} by the top-level config:",
+            "skipped".yellow()
+        )?;
+        for rejection in rejections {
+            writeln!(printer.stdout(), "  - {rejection}")?;
+        }
+        return Ok(ExitStatus::Success);
+    }
+
+    for hook in &hooks {
+        let rejections = FileFilter::explain(hook, &filename, &tags)?;
+        if rejections.is_empty() {
+            writeln!(printer.stdout(), "{hook:#}: {}", "runs".green())?;
+        } else {
+            writeln!(printer.stdout(), "{hook:#}: {}", "skipped".yellow())?;
+            for rejection in rejections {
+                writeln!(printer.stdout(), "  - {rejection}")?;
+            }
+        }
+    }
+
+    Ok(ExitStatus::Success)
+}
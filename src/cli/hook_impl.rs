@@ -1,30 +1,54 @@
-use anyhow::Result;
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::fmt::Write as _;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 
 use anstream::eprintln;
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use tokio::io::AsyncWriteExt;
 
 use constants::env_vars::EnvVars;
 
 use crate::cli::{self, ExitStatus, RunArgs};
 use crate::config::HookType;
+use crate::fs::Simplified;
+use crate::git;
 use crate::printer::Printer;
+use crate::process::Cmd;
 
 pub(crate) async fn hook_impl(
     config: Option<PathBuf>,
     hook_type: HookType,
-    _hook_dir: PathBuf,
+    hook_dir: PathBuf,
     skip_on_missing_config: bool,
     args: Vec<OsString>,
     printer: Printer,
 ) -> Result<ExitStatus> {
-    // TODO: run in legacy mode
+    if EnvVars::is_set(EnvVars::PREFLIGIT_INTERNAL__RUNNING_LEGACY) {
+        anyhow::bail!(
+            "prefligit is installed as its own legacy hook\nhint: run `prefligit install --overwrite --hook-type {hook_type}` to fix this"
+        );
+    }
+
+    // `pre-push` hooks receive the pushed refs on stdin, which is shared with the legacy hook.
+    let mut stdin = String::new();
+    if matches!(hook_type, HookType::PrePush) {
+        std::io::stdin().read_to_string(&mut stdin)?;
+    }
+
+    let legacy_success = run_legacy(hook_type, &hook_dir, &args, &stdin, printer).await?;
+    let with_legacy = |status: ExitStatus| match status {
+        ExitStatus::Success if !legacy_success => ExitStatus::Failure,
+        status => status,
+    };
 
     if let Some(ref config_file) = config {
         if !config_file.try_exists()? {
             return if skip_on_missing_config || EnvVars::is_set(EnvVars::PREFLIGIT_ALLOW_NO_CONFIG)
             {
-                Ok(ExitStatus::Success)
+                Ok(with_legacy(ExitStatus::Success))
             } else {
                 eprintln!("Config file not found: {}", config_file.display());
                 eprintln!(
@@ -45,11 +69,17 @@ pub(crate) async fn hook_impl(
         return Ok(ExitStatus::Failure);
     }
 
-    let run_args = to_run_args(hook_type, &args);
+    let mut run_args = to_run_args(hook_type, &args);
+
+    if matches!(hook_type, HookType::PrePush) && !pre_push_ranges(&mut run_args, &stdin).await? {
+        // Nothing to push.
+        return Ok(with_legacy(ExitStatus::Success));
+    }
 
-    cli::run(
+    let status = cli::run(
         config,
         run_args.hook_id,
+        vec![],
         Some(hook_type.into()),
         run_args.from_ref,
         run_args.to_ref,
@@ -58,9 +88,69 @@ pub(crate) async fn hook_impl(
         false,
         run_args.extra,
         false,
+        None,
+        false,
         printer,
     )
-    .await
+    .await?;
+
+    Ok(with_legacy(status))
+}
+
+/// Run the hook that existed before `prefligit install`, which was moved to `<hook_type>.legacy`.
+///
+/// Returns `false` if the legacy hook failed.
+async fn run_legacy(
+    hook_type: HookType,
+    hook_dir: &Path,
+    args: &[OsString],
+    stdin: &str,
+    printer: Printer,
+) -> Result<bool> {
+    let legacy_hook = hook_dir.join(format!("{}.legacy", hook_type.as_str()));
+    if !is_executable(&legacy_hook) {
+        return Ok(true);
+    }
+
+    let mut child = Cmd::new(&legacy_hook, "run legacy hook")
+        .args(args)
+        .env(EnvVars::PREFLIGIT_INTERNAL__RUNNING_LEGACY, "1")
+        .stdin(Stdio::piped())
+        .check(false)
+        .spawn()?;
+    if let Some(mut child_stdin) = child.stdin.take() {
+        // The legacy hook may exit without reading its stdin.
+        match child_stdin.write_all(stdin.as_bytes()).await {
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    let status = child.wait().await?;
+
+    let mut message = format!("Legacy hook {}", legacy_hook.user_display().cyan());
+    if status.success() {
+        write!(message, " {}", "passed".green())?;
+    } else if let Some(code) = status.code() {
+        write!(message, " {} with exit code {code}", "failed".red())?;
+    } else {
+        write!(message, " {}", "failed".red())?;
+    }
+    writeln!(printer.stdout(), "{message}")?;
+
+    Ok(status.success())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
 }
 
 fn to_run_args(hook_type: HookType, args: &[OsString]) -> RunArgs {
@@ -70,7 +160,6 @@ fn to_run_args(hook_type: HookType, args: &[OsString]) -> RunArgs {
         HookType::PrePush => {
             run_args.extra.remote_name = Some(args[0].to_string_lossy().into_owned());
             run_args.extra.remote_url = Some(args[1].to_string_lossy().into_owned());
-            // TODO: implement pre-push
         }
         HookType::CommitMsg => {
             run_args.extra.commit_msg_filename = Some(PathBuf::from(&args[0]));
@@ -105,3 +194,128 @@ fn to_run_args(hook_type: HookType, args: &[OsString]) -> RunArgs {
 
     run_args
 }
+
+/// A ref being pushed, as written by git to the stdin of the `pre-push` hook.
+#[derive(Debug, PartialEq, Eq)]
+struct PushedRef<'a> {
+    local_ref: &'a str,
+    local_sha: &'a str,
+    remote_ref: &'a str,
+    remote_sha: &'a str,
+}
+
+/// Parse the `<local ref> <local sha> <remote ref> <remote sha>` lines of the `pre-push` stdin.
+fn parse_pushed_refs(stdin: &str) -> Vec<PushedRef<'_>> {
+    stdin
+        .lines()
+        .filter_map(|line| {
+            // Split from the right like pre-commit, the local ref is passed as written by the user.
+            let mut parts = line.rsplitn(4, ' ');
+            let remote_sha = parts.next()?;
+            let remote_ref = parts.next()?;
+            let local_sha = parts.next()?;
+            let local_ref = parts.next()?;
+            Some(PushedRef {
+                local_ref,
+                local_sha,
+                remote_ref,
+                remote_sha,
+            })
+        })
+        .collect()
+}
+
+/// Returns true if the sha is the all-zero object name git uses for a missing ref.
+fn is_null_sha(sha: &str) -> bool {
+    sha.bytes().all(|b| b == b'0')
+}
+
+/// Compute the ranges of commits being pushed from the `pre-push` stdin.
+///
+/// Returns `false` if there is nothing to check, e.g. only deleting refs
+/// or pushing commits the remote already has.
+async fn pre_push_ranges(run_args: &mut RunArgs, stdin: &str) -> Result<bool> {
+    let remote_name = run_args.extra.remote_name.clone().unwrap_or_default();
+
+    let mut ranges = Vec::new();
+    let mut all_files = false;
+    let mut pushing = false;
+
+    for pushed in parse_pushed_refs(stdin) {
+        // Deleting the remote ref, nothing to check.
+        if is_null_sha(pushed.local_sha) {
+            continue;
+        }
+
+        if !is_null_sha(pushed.remote_sha) && git::rev_exists(pushed.remote_sha).await? {
+            ranges.push((pushed.remote_sha.to_string(), pushed.local_sha.to_string()));
+        } else {
+            // A new branch, or the remote is ahead of us: check the commits the remote doesn't have yet.
+            let commits = git::get_commits_not_in_remote(pushed.local_sha, &remote_name).await?;
+            let Some(first) = commits.first() else {
+                continue;
+            };
+
+            if git::get_root_commits(pushed.local_sha)
+                .await?
+                .contains(first)
+            {
+                // Pushing the whole history, including the root commit.
+                all_files = true;
+            } else {
+                let source = git::get_parent_commit(first).await?;
+                ranges.push((source, pushed.local_sha.to_string()));
+            }
+        }
+
+        if !pushing {
+            run_args.extra.local_branch = Some(pushed.local_ref.to_string());
+            run_args.extra.remote_branch = Some(pushed.remote_ref.to_string());
+            pushing = true;
+        }
+    }
+
+    if all_files {
+        run_args.all_files = true;
+    } else if let Some((from_ref, to_ref)) = ranges.first() {
+        // `PRE_COMMIT_FROM_REF` and `PRE_COMMIT_TO_REF` are set from the first pushed ref.
+        run_args.from_ref = Some(from_ref.clone());
+        run_args.to_ref = Some(to_ref.clone());
+        run_args.extra.push_ranges = ranges;
+    }
+
+    Ok(pushing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PushedRef, is_null_sha, parse_pushed_refs};
+
+    #[test]
+    fn pushed_refs() {
+        let stdin = "\
+refs/heads/main 1111111111111111111111111111111111111111 refs/heads/main 2222222222222222222222222222222222222222
+HEAD~1 3333333333333333333333333333333333333333 refs/heads/new 0000000000000000000000000000000000000000
+";
+        assert_eq!(
+            parse_pushed_refs(stdin),
+            vec![
+                PushedRef {
+                    local_ref: "refs/heads/main",
+                    local_sha: "1111111111111111111111111111111111111111",
+                    remote_ref: "refs/heads/main",
+                    remote_sha: "2222222222222222222222222222222222222222",
+                },
+                PushedRef {
+                    local_ref: "HEAD~1",
+                    local_sha: "3333333333333333333333333333333333333333",
+                    remote_ref: "refs/heads/new",
+                    remote_sha: "0000000000000000000000000000000000000000",
+                },
+            ]
+        );
+        assert!(parse_pushed_refs("").is_empty());
+        assert!(is_null_sha("0000000000000000000000000000000000000000"));
+        assert!(!is_null_sha("1111111111111111111111111111111111111111"));
+    }
+}
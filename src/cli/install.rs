@@ -17,21 +17,24 @@ use crate::hook::Project;
 use crate::printer::Printer;
 use crate::store::Store;
 
+#[allow(clippy::fn_params_excessive_bools)]
 pub(crate) async fn install(
     config: Option<PathBuf>,
     hook_types: Vec<HookType>,
     install_hooks: bool,
     overwrite: bool,
     allow_missing_config: bool,
+    use_hooks_path: bool,
     printer: Printer,
     git_dir: Option<&Path>,
 ) -> Result<ExitStatus> {
-    if git_dir.is_none() && git::has_hooks_path_set().await? {
+    if git_dir.is_none() && !use_hooks_path && git::has_hooks_path_set().await? {
         writeln!(
             printer.stderr(),
             indoc::indoc! {"
                 Cowardly refusing to install hooks with `core.hooksPath` set.
                 hint: `git config --unset-all core.hooksPath` to fix this.
+                hint: or use `--hooks-path` to install into it.
             "}
         )?;
         return Ok(ExitStatus::Failure);
@@ -41,32 +44,55 @@ pub(crate) async fn install(
 
     let hooks_path = if let Some(dir) = git_dir {
         dir.join("hooks")
+    } else if use_hooks_path {
+        git::get_hooks_dir().await?
     } else {
+        // Linked worktrees share the hooks of the main worktree in the common dir,
+        // a submodule has its own common dir under the superproject's `.git/modules`.
         git::get_git_common_dir().await?.join("hooks")
     };
 
     fs_err::create_dir_all(&hooks_path)?;
 
+    // A hooks directory outside the repository may be shared with other repositories,
+    // so don't point the hooks to our config file, and skip repositories without one.
+    let shared = git_dir.is_none() && use_hooks_path && is_shared_hooks_dir(&hooks_path).await?;
+    if shared {
+        writeln!(
+            printer.stdout(),
+            "{} is outside the repository, the hooks will skip repositories without a config file.",
+            hooks_path.user_display().cyan()
+        )?;
+    }
+
     let project = Project::from_config_file(config);
-    let config_file = project.as_ref().ok().map(Project::config_file);
+    let config_file = project
+        .as_ref()
+        .ok()
+        .map(Project::config_file)
+        .filter(|_| !shared);
+    let mut success = true;
     for hook_type in hook_types {
-        install_hook_script(
+        success &= install_hook_script(
             config_file,
             hook_type,
             &hooks_path,
             overwrite,
-            allow_missing_config,
+            allow_missing_config || shared,
             printer,
         )?;
     }
+    if !success {
+        return Ok(ExitStatus::Failure);
+    }
 
     if install_hooks {
         let mut project = project?;
         let store = Store::from_settings()?.init()?;
-        let _lock = store.lock_async().await?;
 
         let reporter = HookInitReporter::from(printer);
         let hooks = project.init_hooks(&store, Some(&reporter)).await?;
+        project.warn_unlocked(&hooks);
         let reporter = HookInstallReporter::from(printer);
         run::install_hooks(&hooks, &reporter).await?;
     }
@@ -97,6 +123,15 @@ fn get_hook_types(config_file: Option<PathBuf>, hook_types: Vec<HookType>) -> Ve
     hook_types
 }
 
+/// Returns true if the hooks directory is outside the repository.
+async fn is_shared_hooks_dir(hooks_path: &Path) -> Result<bool> {
+    let hooks_path = dunce::canonicalize(hooks_path)?;
+    let root = dunce::canonicalize(git::get_root().await?)?;
+    let git_dir = dunce::canonicalize(git::get_git_common_dir().await?)?;
+    Ok(!hooks_path.starts_with(root) && !hooks_path.starts_with(git_dir))
+}
+
+/// Install the hook script, returns `false` if an existing hook is in the way.
 fn install_hook_script(
     config_file: Option<&Path>,
     hook_type: HookType,
@@ -104,7 +139,7 @@ fn install_hook_script(
     overwrite: bool,
     skip_on_missing_config: bool,
     printer: Printer,
-) -> Result<()> {
+) -> Result<bool> {
     let hook_path = hooks_path.join(hook_type.as_str());
 
     if hook_path.try_exists()? {
@@ -117,6 +152,17 @@ fn install_hook_script(
         } else {
             if !is_our_script(&hook_path)? {
                 let legacy_path = format!("{}.legacy", hook_path.display());
+                // Don't clobber a previously moved hook, e.g. when another tool reinstalled its hook.
+                if Path::new(&legacy_path).try_exists()? {
+                    writeln!(
+                        printer.stderr(),
+                        "Both {} and {} exist, refusing to replace them.\nhint: remove one of them, or use `--overwrite` to replace {}.",
+                        hook_path.user_display().cyan(),
+                        legacy_path.user_display().yellow(),
+                        hook_path.user_display().cyan(),
+                    )?;
+                    return Ok(false);
+                }
                 fs_err::rename(&hook_path, &legacy_path)?;
                 writeln!(
                     printer.stdout(),
@@ -169,7 +215,7 @@ fn install_hook_script(
         hook_path.user_display().cyan()
     )?;
 
-    Ok(())
+    Ok(true)
 }
 
 static HOOK_TMPL: &str = indoc! { r#"
@@ -204,10 +250,18 @@ fn is_our_script(hook_path: &Path) -> Result<bool> {
 pub(crate) async fn uninstall(
     config: Option<PathBuf>,
     hook_types: Vec<HookType>,
+    use_hooks_path: bool,
     printer: Printer,
 ) -> Result<ExitStatus> {
+    // Uninstall from where `install` installed.
+    let hooks_path = if use_hooks_path {
+        git::get_hooks_dir().await?
+    } else {
+        git::get_git_common_dir().await?.join("hooks")
+    };
+    let hint_hooks_path = !use_hooks_path && git::has_hooks_path_set().await?;
+
     for hook_type in get_hook_types(config, hook_types) {
-        let hooks_path = git::get_git_common_dir().await?.join("hooks");
         let hook_path = hooks_path.join(hook_type.as_str());
         let legacy_path = hooks_path.join(format!("{}.legacy", hook_type.as_str()));
 
@@ -217,6 +271,12 @@ pub(crate) async fn uninstall(
                 "{} does not exist, skipping.",
                 hook_path.user_display().cyan()
             )?;
+            if hint_hooks_path {
+                writeln!(
+                    printer.stderr(),
+                    "hint: use `--hooks-path` to uninstall from the `core.hooksPath` directory."
+                )?;
+            }
         } else if !is_our_script(&hook_path)? {
             writeln!(
                 printer.stderr(),
@@ -258,6 +318,7 @@ pub(crate) async fn init_template_dir(
         false,
         true,
         !requires_config,
+        false,
         printer,
         Some(&directory),
     )
@@ -0,0 +1,139 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::cli::ExitStatus;
+use crate::cli::reporter::HookInitReporter;
+use crate::config::Stage;
+use crate::fs::Simplified;
+use crate::hook::{Hook, Project};
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// A hook as resolved from the manifest and the config.
+#[derive(Serialize)]
+struct HookInfo<'a> {
+    id: &'a str,
+    name: &'a str,
+    alias: &'a str,
+    repo: String,
+    language: &'a str,
+    language_version: String,
+    /// The toolchain version from the lockfile, or of the installed environment.
+    resolved_version: Option<String>,
+    stages: Vec<&'a str>,
+    files: Option<&'a str>,
+    exclude: Option<&'a str>,
+    types: &'a [String],
+    types_or: &'a [String],
+    exclude_types: &'a [String],
+    /// The environment directory, `None` for hooks that don't need one.
+    env_path: Option<String>,
+    installed: bool,
+}
+
+impl<'a> From<&'a Hook> for HookInfo<'a> {
+    fn from(hook: &'a Hook) -> Self {
+        Self {
+            id: &hook.id,
+            name: &hook.name,
+            alias: &hook.alias,
+            repo: hook.repo().to_string(),
+            language: hook.language.as_str(),
+            language_version: hook.language_version.to_string(),
+            resolved_version: hook
+                .lock
+                .as_ref()
+                .map(|lock| lock.resolved_version.clone())
+                .or_else(|| {
+                    hook.installed()
+                        .then(|| hook.language.installed_version(hook))
+                        .flatten()
+                }),
+            stages: hook.stages.iter().map(Stage::as_str).collect(),
+            files: hook.files.as_deref(),
+            exclude: hook.exclude.as_deref(),
+            types: &hook.types,
+            types_or: &hook.types_or,
+            exclude_types: &hook.exclude_types,
+            env_path: hook
+                .env_path()
+                .map(|path| path.simplified_display().to_string()),
+            installed: hook.installed(),
+        }
+    }
+}
+
+pub(crate) async fn list(
+    config: Option<PathBuf>,
+    hook_stage: Option<Stage>,
+    json: bool,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    let reporter = HookInitReporter::from(printer);
+    let hooks = project.init_hooks(&store, Some(&reporter)).await?;
+
+    let hooks: Vec<_> = hooks
+        .iter()
+        .filter(|hook| hook_stage.is_none_or(|stage| hook.stages.contains(&stage)))
+        .map(HookInfo::from)
+        .collect();
+
+    if json {
+        writeln!(
+            printer.stdout(),
+            "{}",
+            serde_json::to_string_pretty(&hooks)?
+        )?;
+        return Ok(ExitStatus::Success);
+    }
+
+    for hook in hooks {
+        writeln!(
+            printer.stdout(),
+            "{} ({})",
+            hook.id.bold(),
+            hook.repo.dimmed()
+        )?;
+
+        let field = |key: &str, value: &str| -> std::fmt::Result {
+            writeln!(printer.stdout(), "  {}: {}", key.dimmed(), value)
+        };
+        field("name", hook.name)?;
+        if !hook.alias.is_empty() {
+            field("alias", hook.alias)?;
+        }
+        let language = match &hook.resolved_version {
+            Some(resolved) => format!(
+                "{} ({}, resolved {resolved})",
+                hook.language, hook.language_version
+            ),
+            None => format!("{} ({})", hook.language, hook.language_version),
+        };
+        field("language", &language)?;
+        field("stages", &hook.stages.join(", "))?;
+        field("files", hook.files.unwrap_or("(all)"))?;
+        field("exclude", hook.exclude.unwrap_or("(none)"))?;
+        field("types", &hook.types.join(", "))?;
+        if !hook.types_or.is_empty() {
+            field("types_or", &hook.types_or.join(", "))?;
+        }
+        if !hook.exclude_types.is_empty() {
+            field("exclude_types", &hook.exclude_types.join(", "))?;
+        }
+        let environment = match (&hook.env_path, hook.installed) {
+            (None, _) => "not needed".to_string(),
+            (Some(path), true) => format!("installed at {path}"),
+            (Some(path), false) => format!("not installed, will be created at {path}"),
+        };
+        field("environment", &environment)?;
+    }
+
+    Ok(ExitStatus::Success)
+}
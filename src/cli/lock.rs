@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::cli::reporter::HookInitReporter;
+use crate::config::{self, LockedRepo, Lockfile, RemoteRepo};
+use crate::fs::Simplified;
+use crate::git;
+use crate::hook::Project;
+use crate::printer::Printer;
+use crate::settings::Settings;
+use crate::store::{Store, is_offline};
+
+/// Resolve the commit `rev` of a remote repo points to.
+async fn resolve_sha(store: &Store, repo: &RemoteRepo) -> Result<String> {
+    if git::is_full_sha(&repo.rev) {
+        return Ok(repo.rev.clone());
+    }
+    if !is_offline() {
+        let url = Settings::get().repo_url(repo.repo.as_str());
+        if let Some((sha, _)) = git::resolve_rev(&url, &repo.rev).await? {
+            return Ok(sha);
+        }
+    }
+    // Commits are not listed by `git ls-remote`, check out the repo to resolve them.
+    let path = store.clone_repo(repo).await?;
+    Ok(git::head_commit(&path).await?)
+}
+
+/// Resolve the revs of the repos and the toolchains and dependencies of the hooks
+/// into the lockfile next to the config.
+pub(crate) async fn lock(config: Option<PathBuf>, printer: Printer) -> Result<ExitStatus> {
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    let mut repos = Vec::new();
+    let mut seen = HashSet::new();
+    for repo in &project.config().repos {
+        let config::Repo::Remote(repo) = repo else {
+            continue;
+        };
+        if !seen.insert(repo) {
+            continue;
+        }
+        let sha = resolve_sha(&store, repo)
+            .await
+            .with_context(|| format!("Failed to resolve `{repo}`"))?;
+        repos.push(LockedRepo {
+            repo: repo.repo.clone(),
+            rev: repo.rev.clone(),
+            sha,
+        });
+    }
+
+    // Clone the resolved commits, and resolve the hooks from scratch.
+    project.set_lockfile(Some(Lockfile {
+        repos: repos.clone(),
+        hooks: Vec::new(),
+    }));
+    let reporter = HookInitReporter::from(printer);
+    let hooks = project.init_hooks(&store, Some(&reporter)).await?;
+
+    let mut locked_hooks = Vec::new();
+    let to_resolve = hooks
+        .iter()
+        .filter(|hook| hook.language.supports_lock() && hook.env_path().is_some())
+        .unique_by(|hook| {
+            (
+                hook.repo().to_string(),
+                &hook.id,
+                hook.language_version.as_str(),
+                &hook.additional_dependencies,
+            )
+        });
+    for hook in to_resolve {
+        let env = hook
+            .language
+            .resolve(hook)
+            .await
+            .with_context(|| format!("Failed to resolve hook `{hook:#}`"))?;
+        locked_hooks.push(hook.locked(env));
+    }
+
+    let lockfile = Lockfile {
+        repos,
+        hooks: locked_hooks,
+    };
+    let path = Lockfile::path(project.config_file());
+    lockfile.write(&path)?;
+
+    writeln!(
+        printer.stdout(),
+        "Locked {} repos and {} hooks in `{}`",
+        lockfile.repos.len(),
+        lockfile.hooks.len(),
+        path.user_display().cyan()
+    )?;
+
+    Ok(ExitStatus::Success)
+}
@@ -1,6 +1,7 @@
 use std::ffi::OsString;
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::Duration;
 
 use clap::builder::Styles;
 use clap::builder::styling::{AnsiColor, Effects};
@@ -8,21 +9,35 @@ use clap::{ArgAction, Args, Parser, Subcommand};
 
 use crate::config::{HookType, Stage};
 
+mod bundle;
+mod cache;
 mod clean;
+mod doctor;
+mod explain;
 mod hook_impl;
 mod install;
+mod list;
+mod lock;
 mod reporter;
 pub mod run;
 mod sample_config;
 mod self_update;
+mod stash;
 mod validate;
 
+pub(crate) use bundle::{bundle_export, bundle_import};
+pub(crate) use cache::{cache_dir, cache_info, cache_prune};
 pub(crate) use clean::clean;
+pub(crate) use doctor::doctor;
+pub(crate) use explain::explain;
 pub(crate) use hook_impl::hook_impl;
 pub(crate) use install::{init_template_dir, install, uninstall};
+pub(crate) use list::list;
+pub(crate) use lock::lock;
 pub(crate) use run::run;
 pub(crate) use sample_config::sample_config;
 pub(crate) use self_update::self_update;
+pub(crate) use stash::{stash_list, stash_restore, stash_show};
 pub(crate) use validate::{validate_configs, validate_manifest};
 
 #[derive(Copy, Clone)]
@@ -112,6 +127,7 @@ pub(crate) struct Cli {
 #[derive(Debug, Parser)]
 #[command(next_help_heading = "Global options", next_display_order = 1000)]
 #[command(disable_help_flag = true, disable_version_flag = true)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct GlobalArgs {
     /// Path to alternate config file.
     #[arg(global = true, short, long, value_parser)]
@@ -137,6 +153,12 @@ pub(crate) struct GlobalArgs {
     #[arg(global = true, long)]
     pub no_progress: bool,
 
+    /// Do not access the network, fail instead of cloning repos or downloading tools.
+    ///
+    /// Can also be set with `PREFLIGIT_OFFLINE`, see `prefligit bundle` to prepare the store.
+    #[arg(global = true, long)]
+    pub(crate) offline: bool,
+
     /// Do not print any output.
     #[arg(global = true, long, short, conflicts_with = "verbose")]
     pub quiet: bool,
@@ -165,6 +187,11 @@ pub(crate) enum Command {
     InstallHooks,
     /// Run hooks.
     Run(Box<RunArgs>),
+    /// List the hooks in the config file, as they are resolved for running.
+    #[command(alias = "hooks")]
+    List(ListArgs),
+    /// Explain why each hook would or would not run on a file.
+    Explain(ExplainArgs),
     /// Uninstall the prefligit script.
     Uninstall(UninstallArgs),
     /// Validate `.pre-commit-config.yaml` files.
@@ -180,11 +207,21 @@ pub(crate) enum Command {
     GC,
     /// Clean out pre-commit files.
     Clean,
+    /// Check the tools used by hooks, the config and the store for problems.
+    Doctor,
+    /// Resolve the repo revs and the hook toolchains and dependencies into `.pre-commit-lock.yaml`.
+    Lock,
     /// Install hook script in a directory intended for use with `git config init.templateDir`.
     #[command(name = "init-templatedir")]
     InitTemplateDir(InitTemplateDirArgs),
     /// Try the pre-commit hooks in the current repo.
     TryRepo(Box<RunArgs>),
+    /// Manage the non-staged changes saved while running hooks.
+    Stash(StashNamespace),
+    /// Inspect and prune the cached repos and hook environments.
+    Cache(CacheNamespace),
+    /// Move the repos and hook environments to machines without network access.
+    Bundle(BundleNamespace),
 
     /// The implementation of the `pre-commit` hook.
     #[command(hide = true)]
@@ -200,6 +237,7 @@ pub(crate) enum Command {
 }
 
 #[derive(Debug, Args)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct InstallArgs {
     /// Overwrite existing hooks.
     #[arg(short = 'f', long)]
@@ -215,12 +253,24 @@ pub(crate) struct InstallArgs {
     /// Allow a missing `pre-commit` configuration file.
     #[arg(long)]
     pub(crate) allow_missing_config: bool,
+
+    /// Install into the directory set by `core.hooksPath`.
+    ///
+    /// Existing hooks in the directory are kept and run before prefligit.
+    /// If the directory is outside the repository, the installed hooks
+    /// skip repositories without a configuration file.
+    #[arg(long)]
+    pub(crate) hooks_path: bool,
 }
 
 #[derive(Debug, Args)]
 pub(crate) struct UninstallArgs {
     #[arg(short = 't', long = "hook-type", value_name = "HOOK_TYPE", value_enum)]
     pub(crate) hook_types: Vec<HookType>,
+
+    /// Uninstall from the directory set by `core.hooksPath`, where `install --hooks-path` installed.
+    #[arg(long)]
+    pub(crate) hooks_path: bool,
 }
 
 #[derive(Debug, Clone, Default, Args)]
@@ -249,13 +299,20 @@ pub(crate) struct RunExtraArgs {
     pub(crate) is_squash_merge: bool,
     #[arg(long, hide = true)]
     pub(crate) rewrite_command: Option<String>,
+    /// The `from_ref...to_ref` ranges of all refs being pushed, for `pre-push` hooks.
+    #[arg(skip)]
+    pub(crate) push_ranges: Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone, Default, Args)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct RunArgs {
     /// The hook ID to run.
     #[arg(value_name = "HOOK")]
     pub(crate) hook_id: Option<String>,
+    /// Run the hooks in the group, can be used multiple times.
+    #[arg(long = "group", value_name = "GROUP")]
+    pub(crate) groups: Vec<String>,
     /// Run on all files in the repo.
     #[arg(short, long, conflicts_with_all = ["files", "from_ref", "to_ref"])]
     pub(crate) all_files: bool,
@@ -276,11 +333,34 @@ pub(crate) struct RunArgs {
     /// When hooks fail, run `git diff` directly afterward.
     #[arg(long)]
     pub(crate) show_diff_on_failure: bool,
+    /// Run hooks under a pseudo-terminal when colors are enabled, so they keep their colors.
+    ///
+    /// Only supported on Unix. Can also be enabled with `PREFLIGIT_PTY`.
+    #[arg(long)]
+    pub(crate) pty: bool,
+    /// Show the output of hooks line by line as it is produced, prefixed with the hook id.
+    ///
+    /// Enabled by default in verbose mode when stdout is a terminal.
+    #[arg(long, overrides_with = "no_stream")]
+    pub(crate) stream: bool,
+    #[arg(long, overrides_with = "stream", hide = true)]
+    pub(crate) no_stream: bool,
 
     #[command(flatten)]
     pub(crate) extra: RunExtraArgs,
 }
 
+impl RunArgs {
+    /// Whether to stream the hook output, `None` to decide for each hook.
+    pub(crate) fn stream(&self) -> Option<bool> {
+        match (self.stream, self.no_stream) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 pub(crate) struct ValidateConfigArgs {
     /// The path to the configuration file.
@@ -319,6 +399,94 @@ pub(crate) struct HookImplArgs {
     pub(crate) args: Vec<OsString>,
 }
 
+#[derive(Debug, Args)]
+pub(crate) struct StashNamespace {
+    #[command(subcommand)]
+    pub(crate) command: StashCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum StashCommand {
+    /// List the saved patches with the time and worktree they were saved from.
+    List,
+    /// Show the content of a saved patch.
+    Show(StashArgs),
+    /// Apply a saved patch to the working tree, and remove it once applied.
+    Restore(StashArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ListArgs {
+    /// Only list the hooks that run in the stage.
+    #[arg(long = "stage", alias = "hook-stage", value_name = "STAGE")]
+    pub(crate) hook_stage: Option<Stage>,
+    /// Print the hooks as JSON.
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ExplainArgs {
+    /// The file to check against the hooks' `files`, `exclude` and types.
+    pub(crate) file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct StashArgs {
+    /// The id of the patch, as shown by `prefligit stash list`.
+    pub(crate) id: String,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CacheNamespace {
+    #[command(subcommand)]
+    pub(crate) command: CacheCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum CacheCommand {
+    /// Show the path of the store.
+    Dir,
+    /// Show the disk usage of the store, and each cached repo and hook environment.
+    Info,
+    /// Remove cached repos and hook environments, by age or by repo.
+    Prune(CachePruneArgs),
+}
+
+#[derive(Debug, Args)]
+#[command(group = clap::ArgGroup::new("filter").required(true).multiple(true))]
+pub(crate) struct CachePruneArgs {
+    /// Remove the entries not used for this long, e.g. `30d`, `12h` or `2w`.
+    #[arg(long, value_name = "AGE", value_parser = cache::parse_age, group = "filter")]
+    pub(crate) older_than: Option<Duration>,
+    /// Remove the entries of the repo URL, at any rev.
+    #[arg(long, value_name = "URL", group = "filter")]
+    pub(crate) repo: Option<String>,
+    /// Show what would be removed without removing anything.
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct BundleNamespace {
+    #[command(subcommand)]
+    pub(crate) command: BundleCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum BundleCommand {
+    /// Package the repos, hook environments and toolchains needed by the config into a `.tar.gz` file.
+    Export(BundleArgs),
+    /// Unpack a bundle into the store, use with `--offline` afterward.
+    Import(BundleArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct BundleArgs {
+    /// The bundle file.
+    pub(crate) file: PathBuf,
+}
+
 #[derive(Debug, Args)]
 pub struct SelfNamespace {
     #[command(subcommand)]
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+use std::fmt::Display;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
@@ -45,6 +47,49 @@ impl FilenameFilter {
     pub fn from_hook(hook: &Hook) -> Result<Self, Box<regex::Error>> {
         Self::new(hook.files.as_deref(), hook.exclude.as_deref())
     }
+
+    /// All the patterns rejecting the filename, unlike [`Self::filter`] which stops at the first.
+    pub fn rejections(&self, filename: &str) -> Vec<Rejection> {
+        let mut rejections = Vec::new();
+        if let Some(re) = &self.include {
+            if !re.is_match(filename).unwrap_or(false) {
+                rejections.push(Rejection::NotIncluded(re.as_str().to_string()));
+            }
+        }
+        if let Some(re) = &self.exclude {
+            if re.is_match(filename).unwrap_or(false) {
+                rejections.push(Rejection::Excluded(re.as_str().to_string()));
+            }
+        }
+        rejections
+    }
+}
+
+/// A rule rejecting a file.
+#[derive(Debug)]
+pub enum Rejection {
+    /// The file does not match the `files` pattern.
+    NotIncluded(String),
+    /// The file matches the `exclude` pattern.
+    Excluded(String),
+    /// The file lacks some tags of `types`.
+    MissingTypes(Vec<String>),
+    /// The file has none of the tags of `types_or`.
+    MissingTypesOr(Vec<String>),
+    /// The file has some tags of `exclude_types`.
+    ExcludedTypes(Vec<String>),
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotIncluded(pattern) => write!(f, "does not match `files: {pattern}`"),
+            Self::Excluded(pattern) => write!(f, "matches `exclude: {pattern}`"),
+            Self::MissingTypes(tags) => write!(f, "missing `types`: {}", tags.join(", ")),
+            Self::MissingTypesOr(tags) => write!(f, "none of `types_or`: {}", tags.join(", ")),
+            Self::ExcludedTypes(tags) => write!(f, "has `exclude_types`: {}", tags.join(", ")),
+        }
+    }
 }
 
 /// Filter files by tags.
@@ -83,6 +128,33 @@ impl<'a> FileTagFilter<'a> {
     fn from_hook(hook: &'a Hook) -> Self {
         Self::new(&hook.types, &hook.types_or, &hook.exclude_types)
     }
+
+    /// All the tag rules rejecting the file, unlike [`Self::filter`] which stops at the first.
+    fn rejections(&self, file_types: &[&str]) -> Vec<Rejection> {
+        let mut rejections = Vec::new();
+        let missing: Vec<_> = self
+            .all
+            .iter()
+            .filter(|t| !file_types.contains(&t.as_str()))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            rejections.push(Rejection::MissingTypes(missing));
+        }
+        if !self.any.is_empty() && !self.any.iter().any(|t| file_types.contains(&t.as_str())) {
+            rejections.push(Rejection::MissingTypesOr(self.any.to_vec()));
+        }
+        let excluded: Vec<_> = self
+            .exclude
+            .iter()
+            .filter(|t| file_types.contains(&t.as_str()))
+            .cloned()
+            .collect();
+        if !excluded.is_empty() {
+            rejections.push(Rejection::ExcludedTypes(excluded));
+        }
+        rejections
+    }
 }
 
 pub struct FileFilter<'a> {
@@ -161,6 +233,19 @@ impl<'a> FileFilter<'a> {
 
         Ok(filenames)
     }
+
+    /// Explain why the hook would not run on the file with the given tags, an empty list means it would.
+    ///
+    /// The top-level `files` and `exclude` patterns are not included, see [`FilenameFilter::rejections`].
+    pub fn explain(
+        hook: &Hook,
+        filename: &str,
+        file_types: &[&str],
+    ) -> Result<Vec<Rejection>, Box<regex::Error>> {
+        let mut rejections = FilenameFilter::from_hook(hook)?.rejections(filename);
+        rejections.extend(FileTagFilter::from_hook(hook).rejections(file_types));
+        Ok(rejections)
+    }
 }
 
 #[derive(Default)]
@@ -168,6 +253,7 @@ pub struct CollectOptions {
     pub hook_stage: Option<Stage>,
     pub from_ref: Option<String>,
     pub to_ref: Option<String>,
+    pub push_ranges: Vec<(String, String)>,
     pub all_files: bool,
     pub files: Vec<PathBuf>,
     pub commit_msg_filename: Option<PathBuf>,
@@ -187,6 +273,7 @@ pub async fn collect_files(opts: CollectOptions) -> Result<Vec<String>> {
         hook_stage,
         from_ref,
         to_ref,
+        push_ranges,
         all_files,
         files,
         commit_msg_filename,
@@ -196,6 +283,7 @@ pub async fn collect_files(opts: CollectOptions) -> Result<Vec<String>> {
         hook_stage,
         from_ref,
         to_ref,
+        push_ranges,
         all_files,
         files,
         commit_msg_filename,
@@ -218,6 +306,7 @@ async fn collect_files_from_args(
     hook_stage: Option<Stage>,
     from_ref: Option<String>,
     to_ref: Option<String>,
+    push_ranges: Vec<(String, String)>,
     all_files: bool,
     files: Vec<PathBuf>,
     commit_msg_filename: Option<PathBuf>,
@@ -236,6 +325,23 @@ async fn collect_files_from_args(
         }
     }
 
+    // Run on the union of files changed by all pushed refs.
+    if !push_ranges.is_empty() {
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for (from_ref, to_ref) in &push_ranges {
+            let changed = git::get_changed_files(from_ref, to_ref).await?;
+            debug!(
+                "Files changed between {} and {}: {}",
+                from_ref,
+                to_ref,
+                changed.len()
+            );
+            files.extend(changed.into_iter().filter(|file| seen.insert(file.clone())));
+        }
+        return Ok(files);
+    }
+
     if let (Some(from_ref), Some(to_ref)) = (from_ref, to_ref) {
         let files = git::get_changed_files(&from_ref, &to_ref).await?;
         debug!(
@@ -12,11 +12,12 @@ use constants::env_vars::EnvVars;
 use crate::cleanup::add_cleanup;
 use crate::fs::Simplified;
 use crate::git::{self, GIT, git_cmd};
+use crate::run::Partitions;
 use crate::store::Store;
 
 static RESTORE_WORKTREE: Mutex<Option<WorkTreeKeeper>> = Mutex::new(None);
 
-struct IntentToAddKeeper(Vec<PathBuf>);
+struct IntentToAddKeeper(Vec<String>);
 struct WorkingTreeKeeper(Option<PathBuf>);
 
 impl IntentToAddKeeper {
@@ -26,33 +27,38 @@ impl IntentToAddKeeper {
             return Ok(Self(vec![]));
         }
 
-        // TODO: xargs
-        git_cmd("git rm")?
-            .arg("rm")
-            .arg("--cached")
-            .arg("--")
-            .args(&files)
-            .check(true)
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()
-            .await?;
+        git::output_by_batch(&files, || {
+            let mut cmd = git_cmd("git rm")?;
+            cmd.arg("rm")
+                .arg("--cached")
+                .check(true)
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null());
+            Ok(cmd)
+        })
+        .await?;
 
-        Ok(Self(files.into_iter().map(PathBuf::from).collect()))
+        Ok(Self(files))
     }
 
     fn restore(&self) -> Result<()> {
         // Restore the intent-to-add changes.
-        if !self.0.is_empty() {
-            Command::new(GIT.as_ref()?)
-                .arg("add")
+        let git_add = || -> Result<Command> {
+            let mut cmd = Command::new(GIT.as_ref()?);
+            cmd.arg("add")
                 .arg("--intent-to-add")
                 .arg("--")
-                // TODO: xargs
-                .args(&self.0)
                 .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .status()?;
+                .stderr(std::process::Stdio::null());
+            Ok(cmd)
+        };
+
+        if !self.0.is_empty() {
+            let cmd = git_add()?;
+            let command_line = std::iter::once(cmd.get_program()).chain(cmd.get_args());
+            for batch in Partitions::for_command(command_line, &self.0) {
+                git_add()?.args(batch).status()?;
+            }
         }
         Ok(())
     }
@@ -70,7 +76,7 @@ impl Drop for IntentToAddKeeper {
 }
 
 impl WorkingTreeKeeper {
-    async fn clean(patch_dir: &Path) -> Result<Self> {
+    async fn clean(patch_dir: &Path, root: &Path) -> Result<Self> {
         let tree = git::write_tree().await?;
 
         let mut cmd = git_cmd("git diff-index")?;
@@ -78,6 +84,8 @@ impl WorkingTreeKeeper {
             .arg("diff-index")
             .arg("--ignore-submodules")
             .arg("--binary")
+            // Full object names of the pre-image, for `git apply --3way`.
+            .arg("--full-index")
             .arg("--exit-code")
             .arg("--no-color")
             .arg("--no-ext-diff")
@@ -115,10 +123,15 @@ impl WorkingTreeKeeper {
                     .yellow()
                 );
                 fs_err::create_dir_all(patch_dir)?;
+                // Record where the patches come from, for `prefligit stash list`.
+                fs_err::write(
+                    patch_dir.join(WORKTREE_FILE),
+                    root.to_string_lossy().as_bytes(),
+                )?;
                 fs_err::write(&patch_path, output.stdout)?;
 
                 // Clean the working tree
-                Self::checkout_working_tree()?;
+                checkout_working_tree()?;
 
                 Ok(Self(Some(patch_path)))
             }
@@ -127,65 +140,51 @@ impl WorkingTreeKeeper {
         }
     }
 
-    fn checkout_working_tree() -> Result<()> {
-        let status = Command::new(GIT.as_ref()?)
-            .arg("-c")
-            .arg("submodule.recurse=0")
-            .arg("checkout")
-            .arg("--")
-            .arg(".")
-            // prevent recursive post-checkout hooks
-            .env(EnvVars::PREFLIGIT_INTERNAL__SKIP_POST_CHECKOUT, "1")
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()?;
-        if status.success() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Failed to checkout working tree"))
-        }
-    }
-
-    fn git_apply(patch: &Path) -> Result<()> {
-        let status = Command::new(GIT.as_ref()?)
-            .arg("apply")
-            .arg("--whitespace=nowarn")
-            .arg(patch)
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()?;
-        if status.success() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Failed to apply the patch"))
-        }
-    }
-
     fn restore(&self) -> Result<()> {
         let Some(patch) = self.0.as_ref() else {
             return Ok(());
         };
 
-        // Try to apply the patch
-        if Self::git_apply(patch).is_err() {
-            error!("Failed to apply the patch, rolling back changes");
-            eprintln!(
-                "{}",
-                "Failed to apply the patch, rolling back changes".red()
-            );
+        let restored = match apply_patch(patch) {
+            Ok(restored) => restored,
+            Err(err) => {
+                // Hooks changed the files beyond a merge, prefer the user's changes.
+                error!("Failed to apply the patch: {err}, rolling back changes");
+                eprintln!(
+                    "{}",
+                    "Failed to apply the patch, rolling back changes".red()
+                );
 
-            Self::checkout_working_tree()?;
-            Self::git_apply(patch)?;
+                checkout_working_tree()?;
+                git_apply(patch)?;
+                Restored::Clean
+            }
         };
 
-        eprintln!(
-            "{}",
-            format!(
-                "\nRestored working tree changes from `{}`",
-                patch.user_display()
-            )
-            .yellow()
-        );
+        match restored {
+            Restored::Clean | Restored::Merged => {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "\nRestored working tree changes from `{}`",
+                        patch.user_display()
+                    )
+                    .yellow()
+                );
+                // The changes are back in the working tree, the patch is no longer needed.
+                fs_err::remove_file(patch)?;
+            }
+            Restored::Conflicts => {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "\nRestored working tree changes from `{}` with conflicts, resolve the conflict markers in the files",
+                        patch.user_display()
+                    )
+                    .yellow()
+                );
+            }
+        }
 
         Ok(())
     }
@@ -198,10 +197,134 @@ impl Drop for WorkingTreeKeeper {
                 "{}",
                 format!("Failed to restore working tree changes: {err}").red()
             );
+            if let Some(patch) = &self.0 {
+                eprintln!(
+                    "The changes are kept in `{}`, run `prefligit stash restore {}` to restore them",
+                    patch.user_display().cyan(),
+                    stash_id(patch).cyan(),
+                );
+            }
         }
     }
 }
 
+/// The file in a worktree patch directory recording the path of the worktree.
+pub(crate) const WORKTREE_FILE: &str = "worktree";
+
+/// The id of a saved patch, as used by `prefligit stash`.
+pub(crate) fn stash_id(patch: &Path) -> String {
+    patch
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// How a saved patch was applied back to the working tree.
+pub(crate) enum Restored {
+    /// The patch applied cleanly.
+    Clean,
+    /// The patch was merged with the changes made since it was saved.
+    Merged,
+    /// The patch was merged, but conflicts with the changes made since it was saved.
+    Conflicts,
+}
+
+/// Apply a saved patch to the working tree, leaving the index untouched.
+///
+/// If the files changed since the patch was saved, e.g. hooks modified them,
+/// fall back to a three-way merge.
+pub(crate) fn apply_patch(patch: &Path) -> Result<Restored> {
+    if git_apply(patch).is_ok() {
+        return Ok(Restored::Clean);
+    }
+
+    // `git apply --3way` updates the index too, so merge in a temporary index
+    // with the current working tree staged, as the patched files must match it.
+    let index = tempfile::NamedTempFile::new()?;
+    let output = Command::new(GIT.as_ref()?).arg("write-tree").output()?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to write the index tree");
+    }
+    let tree = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    git_with_index(index.path(), &["read-tree", &tree])?;
+    git_with_index(index.path(), &["add", "--update"])?;
+
+    if git_with_index(
+        index.path(),
+        &[
+            "apply",
+            "--3way",
+            "--whitespace=nowarn",
+            &patch.to_string_lossy(),
+        ],
+    )
+    .is_ok()
+    {
+        return Ok(Restored::Merged);
+    }
+
+    let unmerged = Command::new(GIT.as_ref()?)
+        .arg("ls-files")
+        .arg("--unmerged")
+        .env(EnvVars::GIT_INDEX_FILE, index.path())
+        .output()?;
+    if unmerged.stdout.trim_ascii().is_empty() {
+        Err(anyhow::anyhow!("Failed to apply the patch"))
+    } else {
+        Ok(Restored::Conflicts)
+    }
+}
+
+/// Run a git command against another index file.
+fn git_with_index(index: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new(GIT.as_ref()?)
+        .args(args)
+        .env(EnvVars::GIT_INDEX_FILE, index)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Failed to run `git {}`", args.join(" ")))
+    }
+}
+
+fn checkout_working_tree() -> Result<()> {
+    let status = Command::new(GIT.as_ref()?)
+        .arg("-c")
+        .arg("submodule.recurse=0")
+        .arg("checkout")
+        .arg("--")
+        .arg(".")
+        // prevent recursive post-checkout hooks
+        .env(EnvVars::PREFLIGIT_INTERNAL__SKIP_POST_CHECKOUT, "1")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Failed to checkout working tree"))
+    }
+}
+
+fn git_apply(patch: &Path) -> Result<()> {
+    let status = Command::new(GIT.as_ref()?)
+        .arg("apply")
+        .arg("--whitespace=nowarn")
+        .arg(patch)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Failed to apply the patch"))
+    }
+}
+
 /// Clean Git intent-to-add files and working tree changes, and restore them when dropped.
 pub struct WorkTreeKeeper {
     intent_to_add: Option<IntentToAddKeeper>,
@@ -225,9 +348,14 @@ impl WorkTreeKeeper {
     /// Clear intent-to-add changes from the index and clear the non-staged changes from the working directory.
     /// Restore them when the instance is dropped.
     pub async fn clean(store: &Store) -> Result<RestoreGuard> {
+        // Keep patches per worktree, so concurrent runs in other worktrees never touch them.
+        let git_dir = dunce::canonicalize(git::get_git_dir().await?)?;
+        let patch_dir = store.worktree_patches_dir(&git_dir);
+        let root = dunce::canonicalize(git::get_root().await?)?;
+
         let cleaner = Self {
             intent_to_add: Some(IntentToAddKeeper::clean().await?),
-            working_tree: Some(WorkingTreeKeeper::clean(&store.patches_dir()).await?),
+            working_tree: Some(WorkingTreeKeeper::clean(&patch_dir, &root).await?),
         };
 
         // Set to the global for the cleanup hook.
@@ -1,7 +1,7 @@
-pub use filter::{CollectOptions, FileFilter, collect_files};
+pub use filter::{CollectOptions, FileFilter, FilenameFilter, collect_files};
 pub(crate) use run::{install_hooks, run};
 
 mod filter;
-mod keeper;
+pub(crate) mod keeper;
 #[allow(clippy::module_inception)]
 mod run;
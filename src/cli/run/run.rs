@@ -1,7 +1,7 @@
 use std::cmp::max;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as _;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 
 use anstream::ColorChoice;
@@ -25,12 +25,14 @@ use crate::fs::Simplified;
 use crate::git;
 use crate::hook::{Hook, Project};
 use crate::printer::Printer;
+use crate::run::OutputStream;
 use crate::store::Store;
 
-#[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
 pub(crate) async fn run(
     config: Option<PathBuf>,
     hook_id: Option<String>,
+    groups: Vec<String>,
     hook_stage: Option<Stage>,
     from_ref: Option<String>,
     to_ref: Option<String>,
@@ -39,6 +41,8 @@ pub(crate) async fn run(
     show_diff_on_failure: bool,
     extra_args: RunExtraArgs,
     verbose: bool,
+    stream: Option<bool>,
+    pty: bool,
     printer: Printer,
 ) -> Result<ExitStatus> {
     // Prevent recursive post-checkout hooks.
@@ -69,15 +73,22 @@ pub(crate) async fn run(
         return Ok(ExitStatus::Failure);
     }
 
+    let color = matches!(
+        anstream::AutoStream::choice(&std::io::stdout()),
+        ColorChoice::Always | ColorChoice::AlwaysAnsi
+    );
+    if color && (pty || EnvVars::is_set(EnvVars::PREFLIGIT_PTY)) {
+        crate::run::enable_pty();
+    }
+
     // Set env vars for hooks.
-    let env_vars = fill_envs(from_ref.as_ref(), to_ref.as_ref(), &extra_args);
+    let env_vars = fill_envs(from_ref.as_ref(), to_ref.as_ref(), &extra_args, color);
 
     let mut project = Project::new(config_file)?;
     let store = Store::from_settings()?.init()?;
 
     let reporter = HookInitReporter::from(printer);
 
-    let lock = store.lock_async().await?;
     let hooks = project.init_hooks(&store, Some(&reporter)).await?;
 
     let hooks: Vec<_> = hooks
@@ -96,7 +107,22 @@ pub(crate) async fn run(
                 true
             }
         })
+        .filter(|h| groups.is_empty() || h.group.iter().any(|group| groups.contains(group)))
         .collect();
+    let hooks = order_hooks(hooks);
+    project.warn_unlocked(&hooks);
+
+    if hooks.is_empty() && hook_id.is_none() && !groups.is_empty() {
+        writeln!(
+            printer.stderr(),
+            "No hook found in group {}",
+            groups
+                .iter()
+                .map(|group| format!("`{}`", group.cyan()))
+                .join(", ")
+        )?;
+        return Ok(ExitStatus::Failure);
+    }
 
     if hooks.is_empty() && hook_id.is_some() {
         if let Some(hook_stage) = hook_stage {
@@ -129,7 +155,10 @@ pub(crate) async fn run(
     );
     let reporter = HookInstallReporter::from(printer);
     install_hooks(&to_run, &reporter).await?;
-    drop(lock);
+    {
+        let _lock = store.lock_async().await?;
+        store.mark_as_used(&to_run);
+    }
 
     // Clear any unstaged changes from the git working directory.
     let mut _guard = None;
@@ -141,6 +170,7 @@ pub(crate) async fn run(
         hook_stage,
         from_ref,
         to_ref,
+        push_ranges: extra_args.push_ranges.clone(),
         all_files,
         files,
         commit_msg_filename: extra_args.commit_msg_filename.clone(),
@@ -162,6 +192,7 @@ pub(crate) async fn run(
         project.config().fail_fast.unwrap_or(false),
         show_diff_on_failure,
         verbose,
+        stream,
         printer,
     )
     .await
@@ -186,6 +217,7 @@ fn fill_envs(
     from_ref: Option<&String>,
     to_ref: Option<&String>,
     args: &RunExtraArgs,
+    color: bool,
 ) -> HashMap<&'static str, String> {
     // TODO: how to change these env vars?
     let mut env = HashMap::new();
@@ -232,6 +264,11 @@ fn fill_envs(
     if let Some(ref command) = args.rewrite_command {
         env.insert("PRE_COMMIT_REWRITE_COMMAND", command.clone());
     }
+    if color {
+        // Most tools disable colors when not writing to a terminal, unless forced.
+        env.insert(EnvVars::FORCE_COLOR, "1".into());
+        env.insert(EnvVars::CLICOLOR_FORCE, "1".into());
+    }
 
     env
 }
@@ -249,6 +286,16 @@ fn get_skips() -> Vec<String> {
 }
 
 async fn install_hook(hook: &Hook, env_dir: &Path) -> Result<()> {
+    if let Some(hooks_dir) = env_dir.parent() {
+        fs_err::tokio::create_dir_all(hooks_dir).await?;
+    }
+    let _lock = Store::lock_entry(env_dir, format!("the environment of hook `{hook}`")).await?;
+
+    // Another process may have installed it while we were waiting for the lock.
+    if hook.installed() {
+        return Ok(());
+    }
+
     debug!(%hook, target = %env_dir.display(), "Install environment");
 
     if env_dir.try_exists()? {
@@ -293,6 +340,7 @@ pub async fn install_hooks(hooks: &[Hook], reporter: &HookInstallReporter) -> Re
 
 const SKIPPED: &str = "Skipped";
 const NO_FILES: &str = "(no files to check)";
+const REQUIRES_FAILED: &str = "(required hook failed)";
 
 fn status_line(start: &str, cols: usize, end_msg: &str, end_color: Style, postfix: &str) -> String {
     let dots = cols - start.width_cjk() - end_msg.len() - postfix.len() - 1;
@@ -311,10 +359,50 @@ fn calculate_columns(hooks: &[Hook]) -> usize {
         .map(|hook| hook.name.width_cjk())
         .max()
         .unwrap_or(0);
-    max(80, name_len + 3 + NO_FILES.len() + 1 + SKIPPED.len())
+    max(
+        80,
+        name_len + 3 + max(NO_FILES.len(), REQUIRES_FAILED.len()) + 1 + SKIPPED.len(),
+    )
+}
+
+/// Order the hooks to run after the hooks in their `after` and `requires`,
+/// keeping the config order otherwise.
+fn order_hooks(hooks: Vec<Hook>) -> Vec<Hook> {
+    let dependencies: Vec<Vec<usize>> = hooks
+        .iter()
+        .enumerate()
+        .map(|(i, hook)| {
+            hook.after
+                .iter()
+                .chain(&hook.requires)
+                .flat_map(|id| hooks.iter().positions(move |other| &other.id == id))
+                .filter(|&j| j != i)
+                .collect()
+        })
+        .collect();
+
+    let mut done = vec![false; hooks.len()];
+    let mut order = Vec::with_capacity(hooks.len());
+    while order.len() < hooks.len() {
+        // Cycles are rejected when reading the config, but may come from the manifests,
+        // in that case fall back to the config order.
+        let next = (0..hooks.len())
+            .find(|&i| !done[i] && dependencies[i].iter().all(|&j| done[j]))
+            .or_else(|| (0..hooks.len()).find(|&i| !done[i]))
+            .expect("a hook is left");
+        done[next] = true;
+        order.push(next);
+    }
+
+    let mut hooks: Vec<_> = hooks.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|i| hooks[i].take().expect("each hook is taken once"))
+        .collect()
 }
 
 /// Run all hooks.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_hooks(
     hooks: &[Hook],
     skips: &[String],
@@ -323,21 +411,43 @@ pub async fn run_hooks(
     fail_fast: bool,
     show_diff_on_failure: bool,
     verbose: bool,
+    stream: Option<bool>,
     printer: Printer,
 ) -> Result<ExitStatus> {
     let columns = calculate_columns(hooks);
     let mut success = true;
 
     let mut diff = git::get_diff().await?;
+    // Hooks that failed, or were skipped because a hook they require failed.
+    let mut failed = HashSet::new();
     // hooks must run in serial
     for hook in hooks {
+        if hook.requires.iter().any(|id| failed.contains(id)) {
+            writeln!(
+                printer.stdout(),
+                "{}",
+                status_line(
+                    &hook.name,
+                    columns,
+                    SKIPPED,
+                    Style::new().black().on_yellow(),
+                    REQUIRES_FAILED,
+                )
+            )?;
+            failed.insert(&hook.id);
+            continue;
+        }
+
         let (hook_success, new_diff) = run_hook(
-            hook, filter, &env_vars, skips, diff, columns, verbose, printer,
+            hook, filter, &env_vars, skips, diff, columns, verbose, stream, printer,
         )
         .await?;
 
         success &= hook_success;
         diff = new_diff;
+        if !hook_success {
+            failed.insert(&hook.id);
+        }
         if !success && (fail_fast || hook.fail_fast) {
             break;
         }
@@ -376,6 +486,7 @@ fn shuffle<T>(filenames: &mut [T]) {
     filenames.shuffle(&mut rng);
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_hook(
     hook: &Hook,
     filter: &FileFilter<'_>,
@@ -384,6 +495,7 @@ async fn run_hook(
     diff: Vec<u8>,
     columns: usize,
     verbose: bool,
+    stream: Option<bool>,
     printer: Printer,
 ) -> Result<(bool, Vec<u8>)> {
     if skips.contains(&hook.id) || skips.contains(&hook.alias) {
@@ -418,21 +530,34 @@ async fn run_hook(
         return Ok((true, diff));
     }
 
-    write!(
-        printer.stdout(),
-        "{}{}",
-        &hook.name,
-        ".".repeat(columns - hook.name.width_cjk() - 6 - 1)
-    )?;
-    std::io::stdout().flush()?;
+    // Stream the output in verbose mode on a terminal, unless it goes to the log file.
+    let stream = stream
+        .unwrap_or_else(|| (verbose || hook.verbose) && std::io::stdout().is_terminal())
+        && hook.log_file.is_none();
+    let stream = stream.then(|| OutputStream::new(hook, printer));
+
+    // The status line is printed after the streamed output.
+    if stream.is_none() {
+        write!(
+            printer.stdout(),
+            "{}{}",
+            &hook.name,
+            ".".repeat(columns - hook.name.width_cjk() - 6 - 1)
+        )?;
+        std::io::stdout().flush()?;
+    }
 
     let start = std::time::Instant::now();
 
     let (status, output) = if hook.pass_filenames {
         shuffle(&mut filenames);
-        hook.language.run(hook, &filenames, env_vars).await?
+        hook.language
+            .run(hook, &filenames, env_vars, stream.as_ref())
+            .await?
     } else {
-        hook.language.run(hook, &[], env_vars).await?
+        hook.language
+            .run(hook, &[], env_vars, stream.as_ref())
+            .await?
     };
 
     let duration = start.elapsed();
@@ -441,7 +566,18 @@ async fn run_hook(
     let file_modified = diff != new_diff;
     let success = status == 0 && !file_modified;
 
-    if success {
+    if stream.is_some() {
+        let (result, style) = if success {
+            ("Passed", Style::new().on_green())
+        } else {
+            ("Failed", Style::new().on_red())
+        };
+        writeln!(
+            printer.stdout(),
+            "{}",
+            status_line(&hook.name, columns, result, style, "")
+        )?;
+    } else if success {
         writeln!(printer.stdout(), "{}", "Passed".on_green())?;
     } else {
         writeln!(printer.stdout(), "{}", "Failed".on_red())?;
@@ -477,14 +613,16 @@ async fn run_hook(
 
         // To be consistent with pre-commit, merge stderr into stdout.
         let stdout = output.trim_ascii();
-        if !stdout.is_empty() {
+        // Streamed output has been shown already, hooks run in-process like builtin hooks
+        // and `fail` hooks don't stream theirs.
+        if !stdout.is_empty() && stream.as_ref().is_none_or(|stream| !stream.streamed()) {
             if let Some(file) = hook.log_file.as_deref() {
                 fs_err::OpenOptions::new()
                     .create(true)
                     .append(true)
                     .open(file)
                     .and_then(|mut f| {
-                        f.write_all(stdout)?;
+                        f.write_all(&anstream::adapter::strip_bytes(stdout).into_vec())?;
                         Ok(())
                     })?;
             } else {
@@ -0,0 +1,192 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::cli::run::keeper::{Restored, WORKTREE_FILE, apply_patch, stash_id};
+use crate::fs::Simplified;
+use crate::git;
+use crate::printer::Printer;
+use crate::store::Store;
+use crate::warn_user;
+
+/// A patch of non-staged changes saved while running hooks.
+pub(crate) struct SavedPatch {
+    id: String,
+    path: PathBuf,
+    /// The worktree the patch was saved from, unknown for patches saved by older versions.
+    worktree: Option<PathBuf>,
+    saved_at: Option<SystemTime>,
+}
+
+/// Collect the saved patches in the store, newest first.
+pub(crate) fn saved_patches(store: &Store) -> Result<Vec<SavedPatch>> {
+    fn collect(dir: &Path, worktree: Option<&Path>, patches: &mut Vec<SavedPatch>) -> Result<()> {
+        for entry in fs_err::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "patch") {
+                continue;
+            }
+            let id = stash_id(&path);
+            // Patches are named `<milliseconds since epoch>-<pid>.patch`.
+            let saved_at = id
+                .split_once('-')
+                .and_then(|(millis, _)| millis.parse().ok())
+                .map(|millis| SystemTime::UNIX_EPOCH + Duration::from_millis(millis));
+            patches.push(SavedPatch {
+                id,
+                path,
+                worktree: worktree.map(Path::to_path_buf),
+                saved_at,
+            });
+        }
+        Ok(())
+    }
+
+    let patches_dir = store.patches_dir();
+    let mut patches = Vec::new();
+    if !patches_dir.is_dir() {
+        return Ok(patches);
+    }
+
+    // Patches saved before they were kept per worktree.
+    collect(&patches_dir, None, &mut patches)?;
+    for entry in fs_err::read_dir(&patches_dir)? {
+        let dir = entry?.path();
+        if dir.is_dir() {
+            let worktree = fs_err::read_to_string(dir.join(WORKTREE_FILE))
+                .ok()
+                .map(PathBuf::from);
+            collect(&dir, worktree.as_deref(), &mut patches)?;
+        }
+    }
+
+    patches.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(patches)
+}
+
+fn find_patch(store: &Store, id: &str, printer: Printer) -> Result<Option<SavedPatch>> {
+    let patch = saved_patches(store)?
+        .into_iter()
+        .find(|patch| patch.id == id);
+    if patch.is_none() {
+        writeln!(
+            printer.stderr(),
+            "No saved patch with id `{}`\nhint: run `prefligit stash list` to see the saved patches",
+            id.cyan()
+        )?;
+    }
+    Ok(patch)
+}
+
+pub(crate) fn format_age(saved_at: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(saved_at)
+        .unwrap_or_default()
+        .as_secs();
+    let (n, unit) = match secs {
+        0..60 => (secs, "second"),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    if n == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{n} {unit}s ago")
+    }
+}
+
+pub(crate) fn stash_list(printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+    let patches = saved_patches(&store)?;
+
+    if patches.is_empty() {
+        writeln!(printer.stdout(), "No saved patches")?;
+        return Ok(ExitStatus::Success);
+    }
+
+    for patch in patches {
+        let age = patch
+            .saved_at
+            .map_or_else(|| "unknown time".to_string(), format_age);
+        let worktree = patch.worktree.as_ref().map_or_else(
+            || "unknown worktree".to_string(),
+            |worktree| worktree.simplified_display().to_string(),
+        );
+        writeln!(
+            printer.stdout(),
+            "{}  {}  {}",
+            patch.id.cyan(),
+            age.dimmed(),
+            worktree
+        )?;
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+pub(crate) fn stash_show(id: &str, printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+    let Some(patch) = find_patch(&store, id, printer)? else {
+        return Ok(ExitStatus::Failure);
+    };
+
+    // Patches are text, binary changes are encoded as base85.
+    let content = fs_err::read(&patch.path)?;
+    write!(printer.stdout(), "{}", String::from_utf8_lossy(&content))?;
+
+    Ok(ExitStatus::Success)
+}
+
+pub(crate) async fn stash_restore(id: &str, printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+    let Some(patch) = find_patch(&store, id, printer)? else {
+        return Ok(ExitStatus::Failure);
+    };
+
+    let root = dunce::canonicalize(git::get_root().await?)?;
+    if let Some(worktree) = patch
+        .worktree
+        .as_ref()
+        .filter(|&worktree| *worktree != root)
+    {
+        warn_user!(
+            "The patch was saved from `{}`, applying it to `{}`",
+            worktree.simplified_display(),
+            root.simplified_display()
+        );
+    }
+
+    match apply_patch(&patch.path) {
+        Ok(Restored::Clean | Restored::Merged) => {
+            fs_err::remove_file(&patch.path)?;
+            writeln!(
+                printer.stdout(),
+                "Restored working tree changes from `{}`",
+                patch.id.cyan()
+            )?;
+            Ok(ExitStatus::Success)
+        }
+        Ok(Restored::Conflicts) => {
+            writeln!(
+                printer.stdout(),
+                "Restored working tree changes from `{}` with conflicts, resolve the conflict markers in the files",
+                patch.id.cyan()
+            )?;
+            Ok(ExitStatus::Success)
+        }
+        Err(err) => {
+            writeln!(
+                printer.stderr(),
+                "{}\nhint: the patch is kept at `{}`",
+                format!("Failed to restore working tree changes: {err}").red(),
+                patch.path.user_display()
+            )?;
+            Ok(ExitStatus::Failure)
+        }
+    }
+}
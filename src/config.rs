@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
 use std::ops::RangeInclusive;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::Result;
@@ -14,6 +14,7 @@ use crate::fs::Simplified;
 pub const CONFIG_FILE: &str = ".pre-commit-config.yaml";
 pub const ALTER_CONFIG_FILE: &str = ".pre-commit-config.yml";
 pub const MANIFEST_FILE: &str = ".pre-commit-hooks.yaml";
+pub const LOCK_FILE: &str = ".pre-commit-lock.yaml";
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -66,6 +67,12 @@ impl Language {
         }
     }
 
+    /// Return whether the hook `env` is set while installing the environment,
+    /// so the environment depends on it.
+    pub fn installs_with_env(self) -> bool {
+        matches!(self, Self::Python)
+    }
+
     /// Return whether the language allows specifying the version.
     /// See <https://pre-commit.com/#overriding-language-version>
     pub fn supports_language_version(self) -> bool {
@@ -227,6 +234,9 @@ pub struct Config {
     /// A configuration-wide default for the stages property of hooks.
     /// Default to all stages.
     pub default_stages: Option<Vec<Stage>>,
+    /// A configuration-wide default for the env property of hooks.
+    /// Variables set on a hook take precedence.
+    pub default_env: Option<BTreeMap<String, String>>,
     /// Global file include pattern.
     pub files: Option<String>,
     /// Global file exclude pattern.
@@ -235,10 +245,157 @@ pub struct Config {
     /// Default is false.
     pub fail_fast: Option<bool>,
     pub minimum_pre_commit_version: Option<String>,
+    /// Mirrors or forks of `https://github.com/pre-commit/pre-commit-hooks`,
+    /// whose hooks can use the builtin implementations.
+    pub pre_commit_hooks_mirrors: Option<Vec<Url>>,
     /// Configuration for pre-commit.ci service.
     pub ci: Option<HashMap<String, serde_yaml::Value>>,
 }
 
+/// Check that a hook only sets `env` for a language that runs hooks with it.
+pub(crate) fn check_env(
+    path: &Path,
+    hook: &str,
+    language: Language,
+    options: &HookOptions,
+) -> Result<(), Error> {
+    if options.env.is_some() && !language.supports_env() {
+        return Err(Error::UnsupportedEnv {
+            path: path.user_display().to_string(),
+            hook: hook.to_string(),
+            language,
+        });
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Check that the hooks whose language is set in the config only set `env` if it applies.
+    ///
+    /// The language of the other remote hooks is only known from their manifests.
+    fn check_env(&self, path: &Path) -> Result<(), Error> {
+        for repo in &self.repos {
+            match repo {
+                Repo::Remote(repo) => {
+                    for hook in &repo.hooks {
+                        if let Some(language) = hook.language {
+                            check_env(path, &hook.id, language, &hook.options)?;
+                        }
+                    }
+                }
+                Repo::Local(repo) => {
+                    for hook in &repo.hooks {
+                        check_env(path, &hook.id, hook.language, &hook.options)?;
+                    }
+                }
+                Repo::Meta(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the `after` and `requires` of hooks refer to hooks in the config, without cycles.
+    fn check_dependencies(&self, path: &Path) -> Result<(), Error> {
+        let hooks: Vec<(&str, &HookOptions)> = self
+            .repos
+            .iter()
+            .flat_map(|repo| -> Vec<(&str, &HookOptions)> {
+                match repo {
+                    Repo::Remote(repo) => repo
+                        .hooks
+                        .iter()
+                        .map(|hook| (hook.id.as_str(), &hook.options))
+                        .collect(),
+                    Repo::Local(repo) => repo
+                        .hooks
+                        .iter()
+                        .map(|hook| (hook.id.as_str(), &hook.options))
+                        .collect(),
+                    Repo::Meta(repo) => repo
+                        .hooks
+                        .iter()
+                        .map(|hook| (hook.0.id.as_str(), &hook.0.options))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        // The same hook can be used several times, merge their dependencies.
+        let mut graph: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (id, _) in &hooks {
+            graph.entry(id).or_default();
+        }
+        for (id, options) in &hooks {
+            for dependency in options.after.iter().chain(&options.requires).flatten() {
+                if !graph.contains_key(dependency.as_str()) {
+                    return Err(Error::UnknownDependency {
+                        path: path.user_display().to_string(),
+                        hook: (*id).to_string(),
+                        dependency: dependency.clone(),
+                    });
+                }
+                graph.get_mut(id).expect("hook exists").push(dependency);
+            }
+        }
+
+        let mut done = HashSet::new();
+        for id in graph.keys() {
+            if let Some(cycle) = find_cycle(id, &graph, &mut Vec::new(), &mut done) {
+                return Err(Error::DependencyCycle {
+                    path: path.user_display().to_string(),
+                    cycle,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Depth-first search from `id`, a hook on the current path that is reached again is a cycle.
+fn find_cycle<'a>(
+    id: &'a str,
+    graph: &BTreeMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    done: &mut HashSet<&'a str>,
+) -> Option<String> {
+    if let Some(start) = path.iter().position(|&hook| hook == id) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(id);
+        return Some(cycle.join(" -> "));
+    }
+    if !done.insert(id) {
+        return None;
+    }
+    path.push(id);
+    for dependency in &graph[id] {
+        if let Some(cycle) = find_cycle(dependency, graph, path, done) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    None
+}
+
+/// Deserialize a single string or a list of strings.
+fn deserialize_one_or_many<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(one)) => Some(vec![one]),
+        Some(OneOrMany::Many(many)) => Some(many),
+        None => None,
+    })
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RepoLocation {
@@ -362,6 +519,9 @@ pub struct HookOptions {
     pub additional_dependencies: Option<Vec<String>>,
     /// Additional arguments to pass to the hook.
     pub args: Option<Vec<String>>,
+    /// Environment variables to set for the hook.
+    /// `${VAR}` in the values is expanded from the environment.
+    pub env: Option<BTreeMap<String, String>>,
     /// This hook will run even if there are no matching files.
     /// Default is false.
     pub always_run: Option<bool>,
@@ -386,6 +546,17 @@ pub struct HookOptions {
     /// Default all stages are selected.
     /// See <https://pre-commit.com/#confining-hooks-to-run-at-certain-stages>.
     pub stages: Option<Vec<Stage>>,
+    /// Run the hook after these hooks, when they run too.
+    /// Default is `[]`.
+    pub after: Option<Vec<String>>,
+    /// Run the hook after these hooks, and skip it when any of them fails.
+    /// Default is `[]`.
+    pub requires: Option<Vec<String>>,
+    /// The groups the hook belongs to, a single group or a list.
+    /// Select them with `prefligit run --group`.
+    /// Default is `[]`.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub group: Option<Vec<String>>,
     /// Print the output of the hook even if it passes.
     /// Default is false.
     pub verbose: Option<bool>,
@@ -404,6 +575,13 @@ impl HookOptions {
             };
         }
 
+        // Variables set in the project config are added to the ones from the manifest.
+        if let Some(env) = &other.env {
+            self.env
+                .get_or_insert_default()
+                .extend(env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
         update_if_some!(
             alias,
             files,
@@ -421,6 +599,9 @@ impl HookOptions {
             log_file,
             require_serial,
             stages,
+            after,
+            requires,
+            group,
             verbose,
             minimum_pre_commit_version,
         );
@@ -507,6 +688,9 @@ impl<'de> Deserialize<'de> for MetaHook {
                 "entry is not allowed for meta hook",
             ));
         }
+        if hook.options.env.is_some() {
+            return Err(serde::de::Error::custom("env is not allowed for meta hook"));
+        }
 
         let mut defaults = match id {
             MetaHookID::CheckHooksApply => ManifestHook {
@@ -707,6 +891,23 @@ pub enum Error {
 
     #[error("Invalid repo URL: {0}")]
     RepoUrl(#[from] url::ParseError),
+
+    #[error("Hook `{hook}` in `{path}` depends on unknown hook `{dependency}`")]
+    UnknownDependency {
+        path: String,
+        hook: String,
+        dependency: String,
+    },
+
+    #[error("Hooks in `{path}` depend on each other: {cycle}")]
+    DependencyCycle { path: String, cycle: String },
+
+    #[error("Hook `{hook}` in `{path}` sets `env`, but `{language}` hooks do not run with it")]
+    UnsupportedEnv {
+        path: String,
+        hook: String,
+        language: Language,
+    },
 }
 
 /// Read the configuration file from the given path.
@@ -718,8 +919,10 @@ pub fn read_config(path: &Path) -> Result<Config, Error> {
         }
         Err(e) => return Err(e.into()),
     };
-    let config = serde_yaml::from_str(&content)
+    let config: Config = serde_yaml::from_str(&content)
         .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
+    config.check_dependencies(path)?;
+    config.check_env(path)?;
     Ok(config)
 }
 
@@ -732,6 +935,86 @@ pub fn read_manifest(path: &Path) -> Result<Manifest, Error> {
     Ok(manifest)
 }
 
+/// The commit a remote repo `rev` resolved to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedRepo {
+    pub repo: Url,
+    pub rev: String,
+    pub sha: String,
+}
+
+/// The toolchain version and dependencies a hook environment resolved to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LockedEnv {
+    pub resolved_version: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolved_dependencies: Vec<String>,
+}
+
+/// A hook environment, keyed by the repo, id and the settings it is installed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedHook {
+    /// The repo URL, or `local`.
+    pub repo: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    pub id: String,
+    pub language_version: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_dependencies: Vec<String>,
+    #[serde(flatten)]
+    pub env: LockedEnv,
+}
+
+/// The `.pre-commit-lock.yaml` next to the config, written by `prefligit lock`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub repos: Vec<LockedRepo>,
+    #[serde(default)]
+    pub hooks: Vec<LockedHook>,
+}
+
+impl Lockfile {
+    /// The path of the lockfile for a config file.
+    pub fn path(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(LOCK_FILE)
+    }
+
+    /// The commit locked for a remote repo.
+    pub fn sha(&self, repo: &RemoteRepo) -> Option<&str> {
+        self.repos
+            .iter()
+            .find(|locked| locked.repo == repo.repo && locked.rev == repo.rev)
+            .map(|locked| locked.sha.as_str())
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let content = serde_yaml::to_string(self)
+            .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
+        fs_err::write(
+            path,
+            format!("# Generated by `prefligit lock`, do not edit it by hand.\n{content}"),
+        )?;
+        Ok(())
+    }
+}
+
+/// Read the lockfile at the given path, if it exists.
+pub fn read_lockfile(path: &Path) -> Result<Option<Lockfile>, Error> {
+    let content = match fs_err::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let lockfile = serde_yaml::from_str(&content)
+        .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
+    Ok(Some(lockfile))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -770,6 +1053,7 @@ mod tests {
                                         exclude_types: None,
                                         additional_dependencies: None,
                                         args: None,
+                                        env: None,
                                         always_run: None,
                                         fail_fast: None,
                                         pass_filenames: None,
@@ -778,6 +1062,9 @@ mod tests {
                                         log_file: None,
                                         require_serial: None,
                                         stages: None,
+                                        after: None,
+                                        requires: None,
+                                        group: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
                                     },
@@ -789,10 +1076,12 @@ mod tests {
                 default_install_hook_types: None,
                 default_language_version: None,
                 default_stages: None,
+                default_env: None,
                 files: None,
                 exclude: None,
                 fail_fast: None,
                 minimum_pre_commit_version: None,
+                pre_commit_hooks_mirrors: None,
                 ci: None,
             },
         )
@@ -861,6 +1150,7 @@ mod tests {
                                         exclude_types: None,
                                         additional_dependencies: None,
                                         args: None,
+                                        env: None,
                                         always_run: None,
                                         fail_fast: None,
                                         pass_filenames: None,
@@ -869,6 +1159,9 @@ mod tests {
                                         log_file: None,
                                         require_serial: None,
                                         stages: None,
+                                        after: None,
+                                        requires: None,
+                                        group: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
                                     },
@@ -880,10 +1173,12 @@ mod tests {
                 default_install_hook_types: None,
                 default_language_version: None,
                 default_stages: None,
+                default_env: None,
                 files: None,
                 exclude: None,
                 fail_fast: None,
                 minimum_pre_commit_version: None,
+                pre_commit_hooks_mirrors: None,
                 ci: None,
             },
         )
@@ -970,6 +1265,7 @@ mod tests {
                                         exclude_types: None,
                                         additional_dependencies: None,
                                         args: None,
+                                        env: None,
                                         always_run: None,
                                         fail_fast: None,
                                         pass_filenames: None,
@@ -978,6 +1274,9 @@ mod tests {
                                         log_file: None,
                                         require_serial: None,
                                         stages: None,
+                                        after: None,
+                                        requires: None,
+                                        group: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
                                     },
@@ -989,10 +1288,12 @@ mod tests {
                 default_install_hook_types: None,
                 default_language_version: None,
                 default_stages: None,
+                default_env: None,
                 files: None,
                 exclude: None,
                 fail_fast: None,
                 minimum_pre_commit_version: None,
+                pre_commit_hooks_mirrors: None,
                 ci: None,
             },
         )
@@ -1095,6 +1396,7 @@ mod tests {
                                             exclude_types: None,
                                             additional_dependencies: None,
                                             args: None,
+                                            env: None,
                                             always_run: None,
                                             fail_fast: None,
                                             pass_filenames: None,
@@ -1103,6 +1405,9 @@ mod tests {
                                             log_file: None,
                                             require_serial: None,
                                             stages: None,
+                                            after: None,
+                                            requires: None,
+                                            group: None,
                                             verbose: None,
                                             minimum_pre_commit_version: None,
                                         },
@@ -1125,6 +1430,7 @@ mod tests {
                                             exclude_types: None,
                                             additional_dependencies: None,
                                             args: None,
+                                            env: None,
                                             always_run: None,
                                             fail_fast: None,
                                             pass_filenames: None,
@@ -1133,6 +1439,9 @@ mod tests {
                                             log_file: None,
                                             require_serial: None,
                                             stages: None,
+                                            after: None,
+                                            requires: None,
+                                            group: None,
                                             verbose: None,
                                             minimum_pre_commit_version: None,
                                         },
@@ -1153,6 +1462,7 @@ mod tests {
                                             exclude_types: None,
                                             additional_dependencies: None,
                                             args: None,
+                                            env: None,
                                             always_run: None,
                                             fail_fast: None,
                                             pass_filenames: None,
@@ -1161,6 +1471,9 @@ mod tests {
                                             log_file: None,
                                             require_serial: None,
                                             stages: None,
+                                            after: None,
+                                            requires: None,
+                                            group: None,
                                             verbose: Some(
                                                 true,
                                             ),
@@ -1175,10 +1488,12 @@ mod tests {
                 default_install_hook_types: None,
                 default_language_version: None,
                 default_stages: None,
+                default_env: None,
                 files: None,
                 exclude: None,
                 fail_fast: None,
                 minimum_pre_commit_version: None,
+                pre_commit_hooks_mirrors: None,
                 ci: None,
             },
         )
@@ -1229,6 +1544,7 @@ mod tests {
                                         exclude_types: None,
                                         additional_dependencies: None,
                                         args: None,
+                                        env: None,
                                         always_run: None,
                                         fail_fast: None,
                                         pass_filenames: None,
@@ -1239,6 +1555,9 @@ mod tests {
                                         log_file: None,
                                         require_serial: None,
                                         stages: None,
+                                        after: None,
+                                        requires: None,
+                                        group: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
                                     },
@@ -1257,6 +1576,7 @@ mod tests {
                                         exclude_types: None,
                                         additional_dependencies: None,
                                         args: None,
+                                        env: None,
                                         always_run: None,
                                         fail_fast: None,
                                         pass_filenames: None,
@@ -1267,6 +1587,9 @@ mod tests {
                                         log_file: None,
                                         require_serial: None,
                                         stages: None,
+                                        after: None,
+                                        requires: None,
+                                        group: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
                                     },
@@ -1285,6 +1608,7 @@ mod tests {
                                         exclude_types: None,
                                         additional_dependencies: None,
                                         args: None,
+                                        env: None,
                                         always_run: None,
                                         fail_fast: None,
                                         pass_filenames: None,
@@ -1297,6 +1621,9 @@ mod tests {
                                         log_file: None,
                                         require_serial: None,
                                         stages: None,
+                                        after: None,
+                                        requires: None,
+                                        group: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
                                     },
@@ -1308,10 +1635,12 @@ mod tests {
                 default_install_hook_types: None,
                 default_language_version: None,
                 default_stages: None,
+                default_env: None,
                 files: None,
                 exclude: None,
                 fail_fast: None,
                 minimum_pre_commit_version: None,
+                pre_commit_hooks_mirrors: None,
                 ci: None,
             },
         )
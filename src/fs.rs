@@ -21,12 +21,15 @@
 // SOFTWARE.
 
 use std::fmt::Display;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
 use fs2::FileExt;
 use tempfile::NamedTempFile;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, trace};
+
+use crate::warn_user;
 
 pub static CWD: LazyLock<PathBuf> =
     LazyLock::new(|| std::env::current_dir().expect("The current directory must be exist"));
@@ -36,6 +39,47 @@ pub static CWD: LazyLock<PathBuf> =
 pub struct LockedFile(fs_err::File);
 
 impl LockedFile {
+    /// Open the lock file, without truncating the holder written by another process.
+    fn open(path: &Path) -> Result<fs_err::File, std::io::Error> {
+        fs_err::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+    }
+
+    /// Write who holds the lock into the lock file, for the processes waiting for it.
+    fn record_holder(file: &fs_err::File) {
+        let mut args = std::env::args_os();
+        let program = args
+            .next()
+            .as_deref()
+            .map(Path::new)
+            .and_then(Path::file_stem)
+            .map_or_else(
+                || "prefligit".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+        let command = args
+            .next()
+            .map(|arg| format!(" {}", arg.to_string_lossy()))
+            .unwrap_or_default();
+        let holder = format!(
+            "`{program}{command}` (pid {}) in `{}`",
+            std::process::id(),
+            CWD.simplified_display()
+        );
+
+        let mut inner = file.file();
+        if let Err(err) = inner
+            .set_len(0)
+            .and_then(|()| inner.write_all(holder.as_bytes()))
+        {
+            trace!(error = ?err, "Failed to record lock holder");
+        }
+    }
+
     /// Inner implementation for [`LockedFile::acquire_blocking`] and [`LockedFile::acquire`].
     fn lock_file_blocking(file: fs_err::File, resource: &str) -> Result<Self, std::io::Error> {
         trace!(
@@ -46,6 +90,7 @@ impl LockedFile {
         match file.file().try_lock_exclusive() {
             Ok(()) => {
                 debug!(resource, "Acquired lock");
+                Self::record_holder(&file);
                 Ok(Self(file))
             }
             Err(err) => {
@@ -53,10 +98,14 @@ impl LockedFile {
                 if err.kind() != std::io::ErrorKind::WouldBlock {
                     trace!(error = ?err, "Try lock error");
                 }
-                info!(
-                    resource,
-                    path = %file.path().display(),
-                    "Waiting to acquire lock",
+                let holder = std::fs::read_to_string(file.path())
+                    .ok()
+                    .filter(|holder| !holder.is_empty())
+                    .map(|holder| format!(", held by {holder}"))
+                    .unwrap_or_default();
+                warn_user!(
+                    "Waiting to acquire the lock for {resource} at `{}`{holder}",
+                    file.path().simplified_display()
                 );
                 file.file().lock_exclusive().map_err(|err| {
                     // Not an fs_err method, we need to build our own path context
@@ -71,6 +120,7 @@ impl LockedFile {
                 })?;
 
                 debug!(resource, "Acquired lock");
+                Self::record_holder(&file);
                 Ok(Self(file))
             }
         }
@@ -83,7 +133,7 @@ impl LockedFile {
         path: impl AsRef<Path>,
         resource: impl Display,
     ) -> Result<Self, std::io::Error> {
-        let file = fs_err::File::create(path.as_ref())?;
+        let file = Self::open(path.as_ref())?;
         let resource = resource.to_string();
         Self::lock_file_blocking(file, &resource)
     }
@@ -93,14 +143,33 @@ impl LockedFile {
         path: impl AsRef<Path>,
         resource: impl Display,
     ) -> Result<Self, std::io::Error> {
-        let file = fs_err::File::create(path.as_ref())?;
+        let file = Self::open(path.as_ref())?;
         let resource = resource.to_string();
         tokio::task::spawn_blocking(move || Self::lock_file_blocking(file, &resource)).await?
     }
+
+    /// Acquire a cross-process lock without waiting, returns `None` if another process holds it.
+    pub fn try_acquire(
+        path: impl AsRef<Path>,
+        resource: impl Display,
+    ) -> Result<Option<Self>, std::io::Error> {
+        let file = Self::open(path.as_ref())?;
+        match file.file().try_lock_exclusive() {
+            Ok(()) => {
+                debug!(resource = %resource, "Acquired lock");
+                Self::record_holder(&file);
+                Ok(Some(Self(file)))
+            }
+            Err(err) if err.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
 }
 
 impl Drop for LockedFile {
     fn drop(&mut self) {
+        // The holder is only meaningful while the lock is held.
+        self.0.file().set_len(0).ok();
         if let Err(err) = FileExt::unlock(self.0.file()) {
             error!(
                 "Failed to unlock {}; program may be stuck: {}",
@@ -289,3 +358,27 @@ impl<T: AsRef<Path>> Simplified for T {
         path.display()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::LockedFile;
+
+    // Windows locks prevent reading the lock file while it is held.
+    #[cfg(unix)]
+    #[test]
+    fn lock_holder() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(".lock");
+
+        let lock = LockedFile::acquire_blocking(&path, "test")?;
+        let holder = fs_err::read_to_string(&path)?;
+        assert!(holder.contains(&format!("(pid {})", std::process::id())));
+        assert!(LockedFile::try_acquire(&path, "test")?.is_none());
+
+        drop(lock);
+        assert_eq!(fs_err::read_to_string(&path)?, "");
+        assert!(LockedFile::try_acquire(&path, "test")?.is_some());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fmt::Display;
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::process::{Output, Stdio};
+use std::str::FromStr;
 use std::sync::LazyLock;
 
 use anyhow::Result;
@@ -8,8 +11,12 @@ use itertools::Itertools;
 use tokio::io::AsyncWriteExt;
 use tracing::warn;
 
+use constants::env_vars::EnvVars;
+
 use crate::process;
 use crate::process::Cmd;
+use crate::run::Partitions;
+use crate::settings::Settings;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -57,6 +64,25 @@ pub fn git_cmd(summary: &str) -> Result<Cmd, Error> {
     Ok(cmd)
 }
 
+/// Run a git command with the paths appended after `--`, in batches that fit within
+/// the command line length limit, and return the output of each batch.
+///
+/// `cmd` creates the command for each batch. The batches run one after another,
+/// as concurrent git commands would fight over the index lock.
+pub async fn output_by_batch<P: AsRef<OsStr>>(
+    paths: &[P],
+    cmd: impl Fn() -> Result<Cmd, Error>,
+) -> Result<Vec<Output>, Error> {
+    let mut base = cmd()?;
+    base.arg("--");
+
+    let mut outputs = Vec::new();
+    for batch in Partitions::for_command(base.get_command_line(), paths) {
+        outputs.push(cmd()?.arg("--").args(batch).output().await?);
+    }
+    Ok(outputs)
+}
+
 fn zsplit(s: &[u8]) -> Vec<String> {
     s.split(|&b| b == b'\0')
         .filter_map(|slice| {
@@ -97,6 +123,64 @@ pub async fn get_changed_files(old: &str, new: &str) -> Result<Vec<String>, Erro
     Ok(zsplit(&output.stdout))
 }
 
+/// Check whether the commit exists in the local repository.
+pub async fn rev_exists(rev: &str) -> Result<bool, Error> {
+    let status = git_cmd("check rev exists")?
+        .arg("rev-list")
+        .arg("--quiet")
+        .arg(rev)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .check(false)
+        .status()
+        .await?;
+    Ok(status.success())
+}
+
+/// Get the commits reachable from `rev` but not from any ref of `remote`, oldest first.
+pub async fn get_commits_not_in_remote(rev: &str, remote: &str) -> Result<Vec<String>, Error> {
+    let output = git_cmd("get commits not in remote")?
+        .arg("rev-list")
+        .arg(rev)
+        .arg("--topo-order")
+        .arg("--reverse")
+        .arg("--not")
+        .arg(format!("--remotes={remote}"))
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// Get the root commits reachable from `rev`.
+pub async fn get_root_commits(rev: &str) -> Result<HashSet<String>, Error> {
+    let output = git_cmd("get root commits")?
+        .arg("rev-list")
+        .arg("--max-parents=0")
+        .arg(rev)
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// Get the first parent of the commit.
+pub async fn get_parent_commit(rev: &str) -> Result<String, Error> {
+    let output = git_cmd("get parent commit")?
+        .arg("rev-parse")
+        .arg(format!("{rev}^"))
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 pub async fn get_all_files() -> Result<Vec<String>, Error> {
     let output = git_cmd("get git all files")?
         .arg("ls-files")
@@ -107,6 +191,105 @@ pub async fn get_all_files() -> Result<Vec<String>, Error> {
     Ok(zsplit(&output.stdout))
 }
 
+/// A file entry in the git index, as listed by `git ls-files --stage`.
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    /// The file mode recorded in the index, e.g. `100644` or `100755`.
+    pub mode: String,
+    pub path: String,
+}
+
+impl IndexEntry {
+    /// Whether the index marks the file as executable, regardless of the filesystem mode.
+    pub fn is_executable(&self) -> bool {
+        self.mode
+            .bytes()
+            .rev()
+            .take(3)
+            .any(|b| matches!(b, b'1' | b'3' | b'5' | b'7'))
+    }
+}
+
+/// Get the index entries for the given paths.
+pub async fn ls_files_stage(paths: &[&String]) -> Result<Vec<IndexEntry>, Error> {
+    let outputs = output_by_batch(paths, || {
+        let mut cmd = git_cmd("get index entries")?;
+        cmd.arg("ls-files").arg("--stage").arg("-z").check(true);
+        Ok(cmd)
+    })
+    .await?;
+
+    Ok(outputs
+        .iter()
+        .flat_map(|output| zsplit(&output.stdout))
+        .filter_map(|line| {
+            // <mode> SP <object> SP <stage> TAB <file>
+            let (metadata, path) = line.split_once('\t')?;
+            let mode = metadata.split_whitespace().next()?;
+            Some(IndexEntry {
+                mode: mode.to_string(),
+                path: path.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Get the submodules newly added in the diff between `from_ref...to_ref`, or in the staged
+/// changes if no refs are given.
+pub async fn get_added_submodules(
+    refs: Option<(&str, &str)>,
+    paths: &[&String],
+) -> Result<Vec<String>, Error> {
+    let outputs = output_by_batch(paths, || {
+        let mut cmd = git_cmd("get added submodules")?;
+        cmd.arg("diff")
+            .arg("--diff-filter=A")
+            .arg("--raw")
+            .arg("--no-ext-diff") // Disable external diff drivers
+            .arg("-z"); // Use NUL as line terminator
+        if let Some((from_ref, to_ref)) = refs {
+            cmd.arg(format!("{from_ref}...{to_ref}"));
+        } else {
+            cmd.arg("--staged");
+        }
+        cmd.check(true);
+        Ok(cmd)
+    })
+    .await?;
+
+    // :<old mode> SP <new mode> SP <old sha> SP <new sha> SP <status> NUL <path> NUL
+    Ok(outputs
+        .iter()
+        .flat_map(|output| zsplit(&output.stdout).into_iter().tuples::<(_, _)>())
+        .filter(|(metadata, _)| metadata.split(' ').nth(1) == Some("160000"))
+        .map(|(_, path)| path)
+        .collect())
+}
+
+/// Get the short name of the branch `HEAD` points to, or `None` if `HEAD` is detached.
+pub async fn get_current_branch() -> Result<Option<String>, Error> {
+    let output = git_cmd("get current branch")?
+        .arg("symbolic-ref")
+        .arg("--quiet")
+        .arg("HEAD")
+        .check(false)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let ref_name = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(
+        ref_name
+            .trim()
+            .splitn(3, '/')
+            .nth(2)
+            .unwrap_or_default()
+            .to_string(),
+    ))
+}
+
 pub async fn get_git_dir() -> Result<PathBuf, Error> {
     let output = git_cmd("get git dir")?
         .arg("rev-parse")
@@ -262,6 +445,26 @@ pub async fn is_dirty(path: &Path) -> Result<bool, Error> {
     }
 }
 
+/// A git command accessing a remote, through the proxy and with the CA certificates in the settings.
+///
+/// Credentials are never prompted for, the command fails instead.
+fn remote_git_cmd(summary: &str) -> Result<Cmd, Error> {
+    let mut cmd = git_cmd(summary)?;
+    cmd.env(EnvVars::GIT_TERMINAL_PROMPT, "0");
+    let settings = Settings::get();
+    if let Some(proxy) = &settings.proxy {
+        cmd.arg("-c").arg(format!("http.proxy={proxy}"));
+    }
+    if let Some(no_proxy) = &settings.no_proxy {
+        cmd.env(EnvVars::NO_PROXY, no_proxy);
+    }
+    if let Some(ca_cert) = &settings.ca_cert {
+        cmd.arg("-c")
+            .arg(format!("http.sslCAInfo={}", ca_cert.display()));
+    }
+    Ok(cmd)
+}
+
 async fn init_repo(url: &str, path: &Path) -> Result<(), Error> {
     git_cmd("init git repo")?
         .arg("init")
@@ -285,7 +488,7 @@ async fn init_repo(url: &str, path: &Path) -> Result<(), Error> {
 }
 
 async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
-    git_cmd("git shallow clone")?
+    remote_git_cmd("git shallow clone")?
         .current_dir(path)
         .arg("-c")
         .arg("protocol.version=2")
@@ -305,7 +508,7 @@ async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
         .output()
         .await?;
 
-    git_cmd("update git submodules")?
+    remote_git_cmd("update git submodules")?
         .current_dir(path)
         .arg("-c")
         .arg("protocol.version=2")
@@ -322,7 +525,7 @@ async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
 }
 
 async fn full_clone(rev: &str, path: &Path) -> Result<(), Error> {
-    git_cmd("git full clone")?
+    remote_git_cmd("git full clone")?
         .current_dir(path)
         .arg("fetch")
         .arg("origin")
@@ -339,7 +542,7 @@ async fn full_clone(rev: &str, path: &Path) -> Result<(), Error> {
         .output()
         .await?;
 
-    git_cmd("update git submodules")?
+    remote_git_cmd("update git submodules")?
         .current_dir(path)
         .arg("submodule")
         .arg("update")
@@ -363,6 +566,121 @@ pub async fn clone_repo(url: &str, rev: &str, path: &Path) -> Result<(), Error>
     }
 }
 
+/// What the `rev` of a remote repo refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevKind {
+    /// A full commit SHA, which never changes.
+    Sha,
+    /// A tag, which is expected to stay but can be moved.
+    Tag,
+    /// A branch, which moves with every push.
+    Branch,
+    /// Neither of the above, e.g. an abbreviated SHA.
+    Unknown,
+    /// Not resolved yet, the remote was unreachable.
+    Unresolved,
+}
+
+impl RevKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha => "sha",
+            Self::Tag => "tag",
+            Self::Branch => "branch",
+            Self::Unknown => "unknown",
+            Self::Unresolved => "unresolved",
+        }
+    }
+}
+
+impl FromStr for RevKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha" => Ok(Self::Sha),
+            "tag" => Ok(Self::Tag),
+            "branch" => Ok(Self::Branch),
+            "unknown" => Ok(Self::Unknown),
+            "unresolved" => Ok(Self::Unresolved),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for RevKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether `rev` is a full SHA-1 or SHA-256 commit id.
+pub fn is_full_sha(rev: &str) -> bool {
+    matches!(rev.len(), 40 | 64) && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Resolve a branch or tag of a remote repo to the commit it points to, with `git ls-remote`.
+pub async fn resolve_rev(url: &str, rev: &str) -> Result<Option<(String, RevKind)>, Error> {
+    let output = remote_git_cmd("resolve git rev")?
+        .arg("ls-remote")
+        .arg("--")
+        .arg(url)
+        .arg(rev)
+        .check(true)
+        .output()
+        .await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let refs: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect();
+
+    // Annotated tags are listed twice, `^{}` is the commit the tag object points to.
+    let exact = if rev.starts_with("refs/tags/") {
+        RevKind::Tag
+    } else {
+        // `HEAD` or a full ref like `refs/heads/main` or `refs/pull/1/head`.
+        RevKind::Branch
+    };
+    let candidates = [
+        (format!("refs/tags/{rev}^{{}}"), RevKind::Tag),
+        (format!("refs/tags/{rev}"), RevKind::Tag),
+        (format!("refs/heads/{rev}"), RevKind::Branch),
+        (rev.to_string(), exact),
+    ];
+    Ok(candidates.iter().find_map(|(candidate, kind)| {
+        refs.iter()
+            .find(|(_, name)| name == candidate)
+            .map(|(sha, _)| ((*sha).to_string(), *kind))
+    }))
+}
+
+/// Get the commit checked out in a repo.
+pub async fn head_commit(path: &Path) -> Result<String, Error> {
+    let output = git_cmd("get head commit")?
+        .current_dir(path)
+        .arg("rev-parse")
+        .arg("HEAD")
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the directory git runs hooks from, respecting `core.hooksPath`.
+pub async fn get_hooks_dir() -> Result<PathBuf, Error> {
+    let output = git_cmd("get git hooks dir")?
+        .arg("rev-parse")
+        .arg("--git-path")
+        .arg("hooks")
+        .check(true)
+        .output()
+        .await?;
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
 pub async fn has_hooks_path_set() -> Result<bool> {
     let output = git_cmd("get git hooks path")?
         .arg("config")
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
@@ -8,17 +8,19 @@ use std::sync::Mutex;
 use anyhow::Result;
 use clap::ValueEnum;
 use futures::StreamExt;
-use itertools::zip_eq;
+use itertools::{Itertools, zip_eq};
 use thiserror::Error;
 use tracing::{debug, error};
 use url::Url;
 
+use crate::builtin;
 use crate::config::{
-    self, ALTER_CONFIG_FILE, CONFIG_FILE, Config, Language, LanguageVersion, LocalHook,
-    MANIFEST_FILE, ManifestHook, MetaHook, RemoteHook, Stage, read_config, read_manifest,
+    self, ALTER_CONFIG_FILE, CONFIG_FILE, Config, Language, LanguageVersion, LocalHook, LockedEnv,
+    LockedHook, Lockfile, MANIFEST_FILE, ManifestHook, MetaHook, RemoteHook, Stage, read_config,
+    read_lockfile, read_manifest,
 };
 use crate::fs::{CWD, Simplified};
-use crate::store::Store;
+use crate::store::{ResolvedRev, Store};
 use crate::warn_user;
 
 #[derive(Debug, Error)]
@@ -42,7 +44,11 @@ pub enum Repo {
         path: PathBuf,
         url: Url,
         rev: String,
+        /// The commit `rev` is locked to in the lockfile, or resolved to when cloned.
+        sha: Option<String>,
         hooks: Vec<ManifestHook>,
+        /// Whether the repo is `pre-commit/pre-commit-hooks` or a configured mirror of it.
+        pre_commit_hooks: bool,
     },
     Local {
         hooks: Vec<ManifestHook>,
@@ -54,15 +60,24 @@ pub enum Repo {
 
 impl Repo {
     /// Load the remote repo manifest from the path.
-    pub fn remote(url: Url, rev: String, path: PathBuf) -> Result<Self, Error> {
+    pub fn remote(
+        url: Url,
+        rev: String,
+        sha: Option<String>,
+        path: PathBuf,
+        mirrors: &[Url],
+    ) -> Result<Self, Error> {
         let manifest = read_manifest(&path.join(MANIFEST_FILE))?;
         let hooks = manifest.hooks;
+        let pre_commit_hooks = builtin::is_pre_commit_hooks(&url, mirrors);
 
         Ok(Self::Remote {
             path,
             url,
             rev,
+            sha,
             hooks,
+            pre_commit_hooks,
         })
     }
 
@@ -110,6 +125,7 @@ impl Display for Repo {
 pub struct Project {
     config_path: PathBuf,
     config: Config,
+    lockfile: Option<Lockfile>,
     repos: Vec<Rc<Repo>>,
 }
 
@@ -157,10 +173,12 @@ impl Project {
             "Loading project configuration"
         );
         let config = read_config(&config_path)?;
+        let lockfile = read_lockfile(&Lockfile::path(&config_path))?;
         let size = config.repos.len();
         Ok(Self {
             config,
             config_path,
+            lockfile,
             repos: Vec::with_capacity(size),
         })
     }
@@ -173,6 +191,41 @@ impl Project {
         &self.config_path
     }
 
+    pub fn lockfile(&self) -> Option<&Lockfile> {
+        self.lockfile.as_ref()
+    }
+
+    /// Replace the lockfile used to clone repos and install hooks, e.g. while locking.
+    pub fn set_lockfile(&mut self, lockfile: Option<Lockfile>) {
+        self.lockfile = lockfile;
+    }
+
+    /// The ids of the hooks to be installed without an entry in the lockfile, if there is one.
+    fn unlocked<'a>(&self, hooks: &'a [Hook]) -> Vec<&'a str> {
+        if self.lockfile.is_none() {
+            return Vec::new();
+        }
+        hooks
+            .iter()
+            .filter(|hook| hook.language.supports_lock() && hook.env_path().is_some())
+            .filter(|hook| hook.lock.is_none())
+            .map(|hook| hook.id.as_str())
+            .unique()
+            .collect()
+    }
+
+    /// Warn about the hooks to be installed without an entry in the lockfile.
+    pub fn warn_unlocked(&self, hooks: &[Hook]) {
+        let unlocked = self.unlocked(hooks);
+        if !unlocked.is_empty() {
+            warn_user!(
+                "`{}` does not lock hooks {}, run `prefligit lock` to update it",
+                Lockfile::path(&self.config_path).user_display(),
+                unlocked.iter().map(|id| format!("`{id}`")).join(", ")
+            );
+        }
+    }
+
     async fn init_repos(
         &mut self,
         store: &Store,
@@ -182,11 +235,17 @@ impl Project {
         let mut seen = HashSet::new();
 
         // Prepare remote repos in parallel.
+        let mirrors = self
+            .config
+            .pre_commit_hooks_mirrors
+            .as_deref()
+            .unwrap_or_default();
         let remotes_iter = self.config.repos.iter().filter_map(|repo| match repo {
             // Deduplicate remote repos.
             config::Repo::Remote(repo) if seen.insert(repo) => Some(repo),
             _ => None,
         });
+        let lockfile = self.lockfile.as_ref();
         let mut tasks = futures::stream::iter(remotes_iter)
             .map(|repo_config| {
                 let remote_repos = remote_repos.clone();
@@ -195,7 +254,23 @@ impl Project {
                         (reporter, reporter.on_clone_start(&format!("{repo_config}")))
                     });
 
-                    let path = store.clone_repo(repo_config).await.map_err(Box::new)?;
+                    // Clone the locked commit instead of `rev`, which may have moved.
+                    let sha = lockfile
+                        .and_then(|lockfile| lockfile.sha(repo_config))
+                        .map(ToString::to_string);
+                    let path = match &sha {
+                        Some(sha) => {
+                            let locked = config::RemoteRepo {
+                                rev: sha.clone(),
+                                ..repo_config.clone()
+                            };
+                            store.clone_repo(&locked).await
+                        }
+                        None => store.clone_repo(repo_config).await,
+                    }
+                    .map_err(Box::new)?;
+                    // Key the environments by the cloned commit, so a moved tag gets new ones.
+                    let sha = sha.or_else(|| ResolvedRev::read(&path).map(|resolved| resolved.sha));
 
                     if let Some((reporter, progress)) = progress {
                         reporter.on_clone_complete(progress);
@@ -204,7 +279,9 @@ impl Project {
                     let repo = Rc::new(Repo::remote(
                         repo_config.repo.clone(),
                         repo_config.rev.clone(),
+                        sha,
                         path,
+                        mirrors,
                     )?);
                     remote_repos
                         .lock()
@@ -251,10 +328,71 @@ impl Project {
         reporter: Option<&dyn HookInitReporter>,
     ) -> Result<Vec<Hook>, Error> {
         self.init_repos(store, reporter).await?;
+        let hooks = self.build_hooks(store, zip_eq(&self.config.repos, &self.repos))?;
+
+        reporter.map(HookInitReporter::on_complete);
+
+        Ok(hooks)
+    }
+
+    /// Load the hooks of the repos that are already cloned, without cloning the others or
+    /// checking the clones against their remotes.
+    ///
+    /// Returns the hooks and the repos that are not cloned yet.
+    pub fn init_cloned_hooks(&self, store: &Store) -> Result<(Vec<Hook>, Vec<String>), Error> {
+        let mirrors = self
+            .config
+            .pre_commit_hooks_mirrors
+            .as_deref()
+            .unwrap_or_default();
 
+        let mut repos = Vec::with_capacity(self.config.repos.len());
+        let mut not_cloned = Vec::new();
+        for repo_config in &self.config.repos {
+            let repo = match repo_config {
+                config::Repo::Remote(remote) => {
+                    let sha = self
+                        .lockfile
+                        .as_ref()
+                        .and_then(|lockfile| lockfile.sha(remote))
+                        .map(ToString::to_string);
+                    let path = match &sha {
+                        Some(sha) => store.cloned_repo(&config::RemoteRepo {
+                            rev: sha.clone(),
+                            ..remote.clone()
+                        }),
+                        None => store.cloned_repo(remote),
+                    }
+                    .map_err(Box::new)?;
+                    let Some(path) = path else {
+                        not_cloned.push(remote.to_string());
+                        continue;
+                    };
+                    let sha = sha.or_else(|| ResolvedRev::read(&path).map(|resolved| resolved.sha));
+                    Repo::remote(remote.repo.clone(), remote.rev.clone(), sha, path, mirrors)?
+                }
+                config::Repo::Local(local) => Repo::local(local.hooks.clone()),
+                config::Repo::Meta(meta) => Repo::meta(meta.hooks.clone()),
+            };
+            repos.push((repo_config, Rc::new(repo)));
+        }
+
+        let hooks = self.build_hooks(
+            store,
+            repos.iter().map(|(repo_config, repo)| (*repo_config, repo)),
+        )?;
+        Ok((hooks, not_cloned.into_iter().unique().collect()))
+    }
+
+    /// Build the hooks of the repos in the config from their manifests.
+    fn build_hooks<'a>(
+        &self,
+        store: &Store,
+        repos: impl IntoIterator<Item = (&'a config::Repo, &'a Rc<Repo>)>,
+    ) -> Result<Vec<Hook>, Error> {
         let mut hooks = Vec::new();
 
-        for (repo_config, repo) in zip_eq(self.config.repos.iter(), self.repos.iter()) {
+        for (repo_config, repo) in repos {
             match repo_config {
                 config::Repo::Remote(repo_config) => {
                     for hook_config in &repo_config.hooks {
@@ -269,9 +407,16 @@ impl Project {
                         let repo = Rc::clone(repo);
                         let mut builder = HookBuilder::new(repo, hook.clone());
                         builder.update(hook_config);
+                        config::check_env(
+                            &self.config_path,
+                            &hook_config.id,
+                            builder.config.language,
+                            &builder.config.options,
+                        )?;
                         builder.combine(&self.config);
 
                         let mut hook = builder.build();
+                        hook.with_lock(self.lockfile.as_ref());
                         hook.with_path(store.hook_path(&hook));
                         hooks.push(hook);
                     }
@@ -283,6 +428,7 @@ impl Project {
                         builder.combine(&self.config);
 
                         let mut hook = builder.build();
+                        hook.with_lock(self.lockfile.as_ref());
                         hook.with_path(store.hook_path(&hook));
                         hooks.push(hook);
                     }
@@ -295,6 +441,7 @@ impl Project {
                         builder.combine(&self.config);
 
                         let mut hook = builder.build();
+                        hook.with_lock(self.lockfile.as_ref());
                         hook.with_path(store.hook_path(&hook));
                         hooks.push(hook);
                     }
@@ -302,8 +449,6 @@ impl Project {
             }
         }
 
-        reporter.map(HookInitReporter::on_complete);
-
         Ok(hooks)
     }
 }
@@ -355,6 +500,18 @@ impl HookBuilder {
         if options.stages.is_none() {
             options.stages.clone_from(&config.default_stages);
         }
+
+        // Only for the languages that run hooks with it.
+        if let Some(default_env) = config
+            .default_env
+            .as_ref()
+            .filter(|_| language.supports_env())
+        {
+            let env = options.env.get_or_insert_default();
+            for (key, value) in default_env {
+                env.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
     }
 
     /// Fill in the default values for the hook configuration.
@@ -363,6 +520,7 @@ impl HookBuilder {
         options.language_version.get_or_insert_default();
         options.alias.get_or_insert_default();
         options.args.get_or_insert_default();
+        options.env.get_or_insert_default();
         options.types.get_or_insert(vec!["file".to_string()]);
         options.types_or.get_or_insert_default();
         options.exclude_types.get_or_insert_default();
@@ -375,6 +533,9 @@ impl HookBuilder {
             .stages
             .get_or_insert(Stage::value_variants().to_vec());
         options.additional_dependencies.get_or_insert_default();
+        options.after.get_or_insert_default();
+        options.requires.get_or_insert_default();
+        options.group.get_or_insert_default();
     }
 
     /// Check the hook configuration.
@@ -431,6 +592,16 @@ impl HookBuilder {
                 .additional_dependencies
                 .expect("additional_dependencies should not be None"),
             args: options.args.expect("args not set"),
+            env: options
+                .env
+                .expect("env not set")
+                .into_iter()
+                .map(|(key, value)| {
+                    #[allow(clippy::disallowed_methods)]
+                    let value = expand_env(&value, |name| std::env::var(name).ok());
+                    (key, value)
+                })
+                .collect(),
             always_run: options.always_run.expect("always_run not set"),
             fail_fast: options.fail_fast.expect("fail_fast not set"),
             pass_filenames: options.pass_filenames.expect("pass_filenames not set"),
@@ -439,8 +610,12 @@ impl HookBuilder {
             log_file: options.log_file,
             require_serial: options.require_serial.expect("require_serial not set"),
             stages: options.stages.expect("stages not set"),
+            after: options.after.expect("after not set"),
+            requires: options.requires.expect("requires not set"),
+            group: options.group.expect("group not set"),
             verbose: options.verbose.expect("verbose not set"),
             minimum_pre_commit_version: options.minimum_pre_commit_version,
+            lock: None,
         }
     }
 }
@@ -463,6 +638,7 @@ pub struct Hook {
     pub exclude_types: Vec<String>,
     pub additional_dependencies: Vec<String>,
     pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
     pub always_run: bool,
     pub fail_fast: bool,
     pub pass_filenames: bool,
@@ -471,8 +647,13 @@ pub struct Hook {
     pub log_file: Option<String>,
     pub require_serial: bool,
     pub stages: Vec<Stage>,
+    pub after: Vec<String>,
+    pub requires: Vec<String>,
+    pub group: Vec<String>,
     pub verbose: bool,
     pub minimum_pre_commit_version: Option<String>,
+    /// The resolved toolchain and dependencies from the lockfile.
+    pub lock: Option<LockedEnv>,
 }
 
 impl Display for Hook {
@@ -490,6 +671,41 @@ impl Hook {
         self.path = path;
     }
 
+    fn with_lock(&mut self, lockfile: Option<&Lockfile>) {
+        self.lock = lockfile
+            .and_then(|lockfile| lockfile.hooks.iter().find(|locked| self.matches(locked)))
+            .map(|locked| locked.env.clone());
+    }
+
+    /// The lockfile entry of the hook with its resolved environment.
+    pub fn locked(&self, env: LockedEnv) -> LockedHook {
+        let (repo, rev) = match &*self.repo {
+            Repo::Remote { url, rev, .. } => (url.to_string(), Some(rev.clone())),
+            _ => (self.repo.to_string(), None),
+        };
+        LockedHook {
+            repo,
+            rev,
+            id: self.id.clone(),
+            language_version: self.language_version.as_str().to_string(),
+            additional_dependencies: self.additional_dependencies.clone(),
+            env,
+        }
+    }
+
+    /// Whether a lockfile entry was resolved from the same repo and settings as the hook.
+    fn matches(&self, locked: &LockedHook) -> bool {
+        let repo = match &*self.repo {
+            Repo::Remote { url, rev, .. } => {
+                locked.repo == url.as_str() && locked.rev.as_ref() == Some(rev)
+            }
+            _ => locked.repo == self.repo.to_string() && locked.rev.is_none(),
+        };
+        repo && locked.id == self.id
+            && locked.language_version == self.language_version.as_str()
+            && locked.additional_dependencies == self.additional_dependencies
+    }
+
     pub fn repo(&self) -> &Repo {
         &self.repo
     }
@@ -516,8 +732,6 @@ impl Hook {
         matches!(&*self.repo, Repo::Meta { .. })
     }
 
-    // TODO: health check
-
     /// Check if the hook is installed in the environment.
     pub fn installed(&self) -> bool {
         // Hooks that no need to install considered as installed.
@@ -541,9 +755,9 @@ impl Hook {
 impl Hash for Hook {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match &*self.repo {
-            Repo::Remote { url, rev, .. } => {
+            Repo::Remote { url, rev, sha, .. } => {
                 url.hash(state);
-                rev.hash(state);
+                sha.as_ref().unwrap_or(rev).hash(state);
             }
             Repo::Local { .. } => {
                 "local".hash(state);
@@ -552,8 +766,121 @@ impl Hash for Hook {
         }
 
         self.language.as_str().hash(state);
-        // TODO: should we resolve the language version first?
-        self.language_version.as_str().hash(state);
+        // Locked hooks are keyed by the resolved versions, so they get a new environment
+        // once `prefligit lock` resolves newer ones.
+        match &self.lock {
+            Some(lock) => lock.hash(state),
+            None => self.language_version.as_str().hash(state),
+        }
         self.additional_dependencies.hash(state);
+        if self.language.installs_with_env() && !self.env.is_empty() {
+            self.env.hash(state);
+        }
+    }
+}
+
+/// Expand `${VAR}` in `value` with `lookup`, unset variables expand to an empty string.
+fn expand_env(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        expanded.push_str(&rest[..start]);
+        let name = &rest[start + 2..start + 2 + len];
+        expanded.push_str(&lookup(name).unwrap_or_default());
+        rest = &rest[start + 2 + len + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::LockedEnv;
+    use crate::store::Store;
+
+    use super::Project;
+
+    #[tokio::test]
+    async fn lock() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let config = temp.path().join(".pre-commit-config.yaml");
+        fs_err::write(
+            &config,
+            indoc::indoc! {r"
+                repos:
+                  - repo: local
+                    hooks:
+                      - id: locked
+                        name: locked
+                        language: python
+                        language_version: '3.12'
+                        entry: lint
+                      - id: changed
+                        name: changed
+                        language: python
+                        language_version: '3.11'
+                        entry: lint
+                      - id: deps
+                        name: deps
+                        language: python
+                        language_version: '3.12'
+                        entry: lint
+                        additional_dependencies: [black]
+                      - id: system
+                        name: system
+                        language: system
+                        entry: lint
+            "},
+        )?;
+        fs_err::write(
+            temp.path().join(".pre-commit-lock.yaml"),
+            indoc::indoc! {r"
+                hooks:
+                - repo: local
+                  id: locked
+                  language_version: '3.12'
+                  resolved_version: 3.12.4
+                - repo: local
+                  id: changed
+                  language_version: '3.12'
+                  resolved_version: 3.12.4
+                - repo: local
+                  id: deps
+                  language_version: '3.12'
+                  resolved_version: 3.12.4
+            "},
+        )?;
+
+        let store = Store::from_path(temp.path().join("store")).init()?;
+        let mut project = Project::new(config)?;
+        let hooks = project.init_hooks(&store, None).await?;
+
+        // Entries only apply to hooks with the same repo, id and settings.
+        assert_eq!(
+            hooks[0].lock,
+            Some(LockedEnv {
+                resolved_version: "3.12.4".to_string(),
+                resolved_dependencies: Vec::new(),
+            })
+        );
+        assert!(hooks[0].matches(&hooks[0].locked(LockedEnv {
+            resolved_version: "3.12.5".to_string(),
+            resolved_dependencies: Vec::new(),
+        })));
+        assert_eq!(hooks[1].lock, None);
+        assert_eq!(hooks[2].lock, None);
+        assert_eq!(hooks[3].lock, None);
+
+        // Hooks that don't need an environment are not locked.
+        assert_eq!(project.unlocked(&hooks), ["changed", "deps"]);
+
+        // Without a lockfile, nothing is expected to be locked.
+        project.set_lockfile(None);
+        assert!(project.unlocked(&hooks).is_empty());
+
+        Ok(())
     }
 }
@@ -721,12 +721,27 @@ fn tags_from_filename(filename: &Path) -> Vec<&str> {
     result.into_iter().collect()
 }
 
-fn tags_from_interpreter(_interpreter: &[String]) -> Vec<&'static str> {
-    todo!()
+fn tags_from_interpreter(interpreter: &[String]) -> Vec<&'static str> {
+    let Some(interpreter) = interpreter.first() else {
+        return vec![];
+    };
+    let mut name = interpreter
+        .rsplit_once('/')
+        .map_or(interpreter.as_str(), |(_, name)| name);
+
+    // Try "python3.5.2" => "python3.5" => "python3" until one matches.
+    while !name.is_empty() {
+        if let Some(tags) = by_interpreter().get(name) {
+            return tags.clone();
+        }
+        name = name.rsplit_once('.').map_or("", |(name, _)| name);
+    }
+
+    vec![]
 }
 
 #[derive(thiserror::Error, Debug)]
-enum ShebangError {
+pub(crate) enum ShebangError {
     #[error("No shebang found")]
     NoShebang,
     #[error("Shebang contains non-printable characters")]
@@ -739,7 +754,8 @@ enum ShebangError {
     IoError(#[from] std::io::Error),
 }
 
-fn parse_shebang(path: &Path) -> Result<Vec<String>, ShebangError> {
+/// Parse the shebang line of a file into the interpreter command and its arguments.
+pub(crate) fn parse_shebang(path: &Path) -> Result<Vec<String>, ShebangError> {
     let file = std::fs::File::open(path)?;
     let mut reader = std::io::BufReader::new(file);
     let mut line = String::new();
@@ -749,7 +765,10 @@ fn parse_shebang(path: &Path) -> Result<Vec<String>, ShebangError> {
     }
 
     // Require only printable ASCII
-    if line.bytes().any(|b| !(0x20..=0x7E).contains(&b)) {
+    if line
+        .bytes()
+        .any(|b| !(0x20..=0x7E).contains(&b) && !b.is_ascii_whitespace())
+    {
         return Err(ShebangError::NonPrintableChars);
     }
 
@@ -811,4 +830,31 @@ mod tests {
         let tags = super::tags_from_filename(Path::new("data.json"));
         assert_eq!(tags, vec!["json", "text"]);
     }
+
+    #[test]
+    fn tags_from_interpreter() {
+        let tags = super::tags_from_interpreter(&["/usr/bin/python3.12".to_string()]);
+        assert_eq!(tags, vec!["python", "python3"]);
+        let tags = super::tags_from_interpreter(&["bash".to_string(), "-e".to_string()]);
+        assert_eq!(tags, vec!["shell", "bash"]);
+        let tags = super::tags_from_interpreter(&["unknown".to_string()]);
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn parse_shebang() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let path = dir.path().join("script");
+        fs_err::write(&path, "#!/usr/bin/env python3 -u\nprint()\n")?;
+        assert_eq!(super::parse_shebang(&path)?, vec!["python3", "-u"]);
+
+        fs_err::write(&path, "print()\n")?;
+        assert!(matches!(
+            super::parse_shebang(&path),
+            Err(super::ShebangError::NoShebang)
+        ));
+
+        Ok(())
+    }
 }
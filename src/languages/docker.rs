@@ -13,7 +13,8 @@ use crate::fs::CWD;
 use crate::hook::Hook;
 use crate::languages::LanguageImpl;
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{OutputStream, run_batch, run_by_batch};
+use crate::store::is_offline;
 
 const PRE_COMMIT_LABEL: &str = "PRE_COMMIT";
 
@@ -131,10 +132,15 @@ impl Docker {
         Ok(Cow::Borrowed(path))
     }
 
-    pub(crate) async fn docker_run_cmd() -> Result<Cmd> {
+    pub(crate) async fn docker_run_cmd(hook: &Hook) -> Result<Cmd> {
         let mut command = Cmd::new("docker", "run container");
         command.arg("run").arg("--rm");
 
+        // The hook runs in the container, not in the environment of `docker`.
+        for (key, value) in &hook.env {
+            command.arg("--env").arg(format!("{key}={value}"));
+        }
+
         match ColorChoice::global() {
             ColorChoice::Always | ColorChoice::AlwaysAnsi => {
                 command.arg("--tty");
@@ -178,13 +184,22 @@ impl LanguageImpl for Docker {
             anyhow::bail!("Docker does not support additional dependencies");
         }
 
-        Docker::build_docker_image(hook, true).await?;
+        // Use the base images already pulled in offline mode.
+        Docker::build_docker_image(hook, !is_offline()).await?;
         fs_err::tokio::create_dir_all(env).await?;
         Ok(())
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    async fn check_health(&self, hook: &Hook) -> Result<()> {
+        // The image may have been removed by `docker image prune`.
+        Cmd::new("docker", "inspect docker image")
+            .arg("image")
+            .arg("inspect")
+            .arg(Self::docker_tag(hook))
+            .check(true)
+            .output()
+            .await?;
+        Ok(())
     }
 
     async fn run(
@@ -192,6 +207,7 @@ impl LanguageImpl for Docker {
         hook: &Hook,
         filenames: &[&String],
         env_vars: &HashMap<&'static str, String>,
+        stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)> {
         Docker::build_docker_image(hook, false).await?;
 
@@ -199,9 +215,9 @@ impl LanguageImpl for Docker {
 
         let cmds = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
 
-        let run = async move |batch: Vec<String>| {
+        let run = async move |batch: &[&String]| {
             // docker run [OPTIONS] IMAGE [COMMAND] [ARG...]
-            let mut cmd = Docker::docker_run_cmd().await?;
+            let mut cmd = Docker::docker_run_cmd(hook).await?;
             let cmd = cmd
                 .arg("--entrypoint")
                 .arg(&cmds[0])
@@ -212,10 +228,7 @@ impl LanguageImpl for Docker {
                 .check(false)
                 .envs(env_vars);
 
-            let mut output = cmd.output().await?;
-            output.stdout.extend(output.stderr);
-            let code = output.status.code().unwrap_or(1);
-            anyhow::Ok((code, output.stdout))
+            run_batch(cmd, stream).await
         };
 
         let results = run_by_batch(hook, filenames, run).await?;
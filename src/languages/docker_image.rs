@@ -5,7 +5,7 @@ use anyhow::Result;
 use crate::hook::Hook;
 use crate::languages::LanguageImpl;
 use crate::languages::docker::Docker;
-use crate::run::run_by_batch;
+use crate::run::{OutputStream, run_batch, run_by_batch};
 
 #[derive(Debug, Copy, Clone)]
 pub struct DockerImage;
@@ -19,8 +19,8 @@ impl LanguageImpl for DockerImage {
         Ok(())
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    async fn check_health(&self, _hook: &Hook) -> Result<()> {
+        Ok(())
     }
 
     async fn run(
@@ -28,11 +28,12 @@ impl LanguageImpl for DockerImage {
         hook: &Hook,
         filenames: &[&String],
         env_vars: &HashMap<&'static str, String>,
+        stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)> {
         let cmds = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
 
-        let run = async move |batch: Vec<String>| {
-            let mut cmd = Docker::docker_run_cmd().await?;
+        let run = async move |batch: &[&String]| {
+            let mut cmd = Docker::docker_run_cmd(hook).await?;
             let cmd = cmd
                 .args(&cmds[..])
                 .args(&hook.args)
@@ -40,10 +41,7 @@ impl LanguageImpl for DockerImage {
                 .check(false)
                 .envs(env_vars);
 
-            let mut output = cmd.output().await?;
-            output.stdout.extend(output.stderr);
-            let code = output.status.code().unwrap_or(1);
-            anyhow::Ok((code, output.stdout))
+            run_batch(cmd, stream).await
         };
 
         let results = run_by_batch(hook, filenames, run).await?;
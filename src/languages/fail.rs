@@ -4,6 +4,7 @@ use anyhow::Result;
 
 use crate::hook::Hook;
 use crate::languages::LanguageImpl;
+use crate::run::OutputStream;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Fail;
@@ -17,7 +18,7 @@ impl LanguageImpl for Fail {
         Ok(())
     }
 
-    async fn check_health(&self) -> anyhow::Result<()> {
+    async fn check_health(&self, _hook: &Hook) -> anyhow::Result<()> {
         Ok(())
     }
 
@@ -26,6 +27,7 @@ impl LanguageImpl for Fail {
         hook: &Hook,
         filenames: &[&String],
         _env_vars: &HashMap<&'static str, String>,
+        _stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)> {
         let mut out = hook.entry.as_bytes().to_vec();
         out.extend(b"\n\n");
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use anyhow::Result;
 
 use crate::builtin;
-use crate::config::Language;
+use crate::config::{Language, LockedEnv};
 use crate::hook::Hook;
+use crate::run::OutputStream;
 
 mod docker;
 mod docker_image;
@@ -13,6 +15,8 @@ mod node;
 mod python;
 mod system;
 
+pub(crate) use python::{UV_MIN_VERSION, UvInstaller};
+
 static PYTHON: python::Python = python::Python;
 static NODE: node::Node = node::Node;
 static SYSTEM: system::System = system::System;
@@ -27,12 +31,14 @@ trait LanguageImpl {
     /// System and Fail do not.
     fn supports_dependency(&self) -> bool;
     async fn install(&self, hook: &Hook) -> Result<()>;
-    async fn check_health(&self) -> Result<()>;
+    /// Check that the installed environment of the hook still works.
+    async fn check_health(&self, hook: &Hook) -> Result<()>;
     async fn run(
         &self,
         hook: &Hook,
         filenames: &[&String],
         env_vars: &HashMap<&'static str, String>,
+        stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)>;
 }
 
@@ -49,6 +55,46 @@ impl Language {
         }
     }
 
+    /// Whether hooks of the language run with the variables in `env`.
+    pub fn supports_env(self) -> bool {
+        matches!(
+            self,
+            Self::Python | Self::System | Self::Fail | Self::Docker | Self::DockerImage
+        )
+    }
+
+    /// Whether `prefligit lock` can resolve the toolchain and dependencies of the language.
+    pub fn supports_lock(self) -> bool {
+        matches!(self, Self::Python | Self::Node)
+    }
+
+    /// Resolve the toolchain version and dependencies of a hook to record in the lockfile.
+    pub async fn resolve(&self, hook: &Hook) -> Result<LockedEnv> {
+        match self {
+            Self::Python => PYTHON.resolve(hook).await,
+            Self::Node => NODE.resolve(hook).await,
+            _ => anyhow::bail!("Language `{}` does not support locking", self.as_str()),
+        }
+    }
+
+    /// The toolchain version of the installed environment of a hook, if it records one.
+    pub fn installed_version(self, hook: &Hook) -> Option<String> {
+        let env_path = hook.env_path()?;
+        match self {
+            Self::Python => python::Python::installed_version(env_path),
+            _ => None,
+        }
+    }
+
+    /// The toolchain in the store the installed environment of a hook uses, if any.
+    pub async fn toolchain(self, hook: &Hook) -> Result<Option<PathBuf>> {
+        match self {
+            Self::Python => Ok(hook.env_path().and_then(python::Python::toolchain)),
+            Self::Node => NODE.toolchain(hook).await,
+            _ => Ok(None),
+        }
+    }
+
     pub async fn install(&self, hook: &Hook) -> Result<()> {
         match self {
             Self::Python => PYTHON.install(hook).await,
@@ -61,14 +107,14 @@ impl Language {
         }
     }
 
-    pub async fn check_health(&self) -> Result<()> {
+    pub async fn check_health(&self, hook: &Hook) -> Result<()> {
         match self {
-            Self::Python => PYTHON.check_health().await,
-            Self::Node => NODE.check_health().await,
-            Self::System => SYSTEM.check_health().await,
-            Self::Fail => FAIL.check_health().await,
-            Self::Docker => DOCKER.check_health().await,
-            Self::DockerImage => DOCKER_IMAGE.check_health().await,
+            Self::Python => PYTHON.check_health(hook).await,
+            Self::Node => NODE.check_health(hook).await,
+            Self::System => SYSTEM.check_health(hook).await,
+            Self::Fail => FAIL.check_health(hook).await,
+            Self::Docker => DOCKER.check_health(hook).await,
+            Self::DockerImage => DOCKER_IMAGE.check_health(hook).await,
             _ => todo!(),
         }
     }
@@ -78,6 +124,7 @@ impl Language {
         hook: &Hook,
         filenames: &[&String],
         env_vars: &HashMap<&'static str, String>,
+        stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)> {
         // fast path for hooks implemented in Rust
         if builtin::check_fast_path(hook) {
@@ -85,12 +132,12 @@ impl Language {
         }
 
         match self {
-            Self::Python => PYTHON.run(hook, filenames, env_vars).await,
-            Self::Node => NODE.run(hook, filenames, env_vars).await,
-            Self::System => SYSTEM.run(hook, filenames, env_vars).await,
-            Self::Fail => FAIL.run(hook, filenames, env_vars).await,
-            Self::Docker => DOCKER.run(hook, filenames, env_vars).await,
-            Self::DockerImage => DOCKER_IMAGE.run(hook, filenames, env_vars).await,
+            Self::Python => PYTHON.run(hook, filenames, env_vars, stream).await,
+            Self::Node => NODE.run(hook, filenames, env_vars, stream).await,
+            Self::System => SYSTEM.run(hook, filenames, env_vars, stream).await,
+            Self::Fail => FAIL.run(hook, filenames, env_vars, stream).await,
+            Self::Docker => DOCKER.run(hook, filenames, env_vars, stream).await,
+            Self::DockerImage => DOCKER_IMAGE.run(hook, filenames, env_vars, stream).await,
             _ => todo!(),
         }
     }
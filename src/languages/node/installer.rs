@@ -18,6 +18,12 @@ use crate::archive;
 use crate::archive::ArchiveExtension;
 use crate::config::LanguageVersion;
 use crate::fs::LockedFile;
+use crate::process::Cmd;
+use crate::settings::Settings;
+use crate::store::is_offline;
+
+/// Where Node.js releases are downloaded from, unless a mirror is set.
+const NODE_DIST: &str = "https://nodejs.org/dist";
 
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
@@ -219,6 +225,11 @@ impl NodeResult {
         }
     }
 
+    /// The installation directory, `None` for the system Node.js.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
     pub fn node(&self) -> Cow<PathBuf> {
         self.node.as_ref().map(Cow::Borrowed).unwrap_or_else(|| {
             Cow::Owned(
@@ -253,14 +264,24 @@ impl NodeResult {
 pub struct NodeInstaller {
     root: PathBuf,
     client: Client,
+    /// The base URL of `index.json` and the release archives.
+    dist: String,
 }
 
 impl NodeInstaller {
-    pub fn new(root: PathBuf) -> Self {
-        Self {
+    pub fn new(root: PathBuf) -> Result<Self> {
+        let settings = Settings::get();
+        Ok(Self {
             root,
-            client: Client::new(),
-        }
+            client: settings.http_client()?,
+            dist: settings
+                .mirrors
+                .node
+                .as_deref()
+                .unwrap_or(NODE_DIST)
+                .trim_end_matches('/')
+                .to_string(),
+        })
     }
 
     /// Install a version of Node.js.
@@ -294,14 +315,61 @@ impl NodeInstaller {
             return Ok(node);
         }
 
+        if is_offline() {
+            anyhow::bail!(
+                "Node `{version}` is not installed, and cannot be downloaded in offline mode"
+            );
+        }
+
         let resolved_version = self.resolve_version(&version_req).await?;
         trace!(version = %resolved_version, "Installing node");
 
         self.install_node(&resolved_version).await
     }
 
+    /// Resolve the version of Node.js `install` would use, without downloading it.
+    pub async fn resolve(&self, version: &LanguageVersion) -> Result<semver::Version> {
+        if version.allows_system() {
+            if let (Ok(node), Ok(_)) = (which::which("node"), which::which("npm")) {
+                let output = Cmd::new(&node, "get node version")
+                    .arg("--version")
+                    .check(true)
+                    .output()
+                    .await?;
+                // e.g. `v20.12.2`
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                return Ok(semver::Version::parse(
+                    stdout.trim().trim_start_matches('v'),
+                )?);
+            }
+        }
+        if !version.allows_download() {
+            return Err(anyhow::anyhow!(
+                "Node not found on the system and downloading is disabled"
+            ));
+        }
+
+        let version_req = VersionRequest::try_from(version.clone())?;
+        if let Some((version, _)) = self.find_installed(&version_req) {
+            return Ok(version.version);
+        }
+        if is_offline() {
+            anyhow::bail!(
+                "Node `{version}` is not installed, and cannot be resolved in offline mode"
+            );
+        }
+        Ok(self.resolve_version(&version_req).await?.version)
+    }
+
     /// Get the installed version of Node.js.
     fn get_installed(&self, req: &VersionRequest) -> Result<NodeResult> {
+        self.find_installed(req)
+            .map(|(_, path)| NodeResult::from_dir(path))
+            .ok_or(anyhow::anyhow!("No installed node found"))
+    }
+
+    /// Find the latest installed version of Node.js matching the request.
+    fn find_installed(&self, req: &VersionRequest) -> Option<(NodeVersion, PathBuf)> {
         let mut installed = fs_err::read_dir(&self.root)
             .ok()
             .into_iter()
@@ -322,15 +390,7 @@ impl NodeInstaller {
             .sorted_unstable_by(|(a, _), (b, _)| a.version.cmp(&b.version))
             .rev();
 
-        installed
-            .find_map(|(v, path)| {
-                if req.matches(&v) {
-                    Some(NodeResult::from_dir(path))
-                } else {
-                    None
-                }
-            })
-            .ok_or(anyhow::anyhow!("No installed node found"))
+        installed.find(|(v, _)| req.matches(v))
     }
 
     async fn resolve_version(&self, req: &VersionRequest) -> Result<NodeVersion> {
@@ -342,10 +402,17 @@ impl NodeInstaller {
         Ok(version)
     }
 
-    /// List all versions of Node.js available on the Node.js website.
+    /// List all versions of Node.js available on the Node.js website or the mirror.
     async fn list_remote_versions(&self) -> Result<Vec<NodeVersion>> {
-        let url = "https://nodejs.org/dist/index.json";
-        let versions: Vec<NodeVersion> = self.client.get(url).send().await?.json().await?;
+        let url = format!("{}/index.json", self.dist);
+        let versions: Vec<NodeVersion> = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
         Ok(versions)
     }
 
@@ -371,7 +438,7 @@ impl NodeInstaller {
         let ext = if cfg!(windows) { "zip" } else { "tar.xz" };
 
         let filename = format!("node-v{}-{os}-{arch}.{ext}", version.version());
-        let url = format!("https://nodejs.org/dist/v{}/{filename}", version.version());
+        let url = format!("{}/v{}/{filename}", self.dist, version.version());
         let target = self.root.join(version.to_string());
 
         let tarball = self
@@ -379,6 +446,7 @@ impl NodeInstaller {
             .get(&url)
             .send()
             .await?
+            .error_for_status()?
             .bytes_stream()
             .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))
             .into_async_read()
@@ -417,3 +485,36 @@ fn bin_dir(root: &Path) -> PathBuf {
 fn bin_dir(root: &Path) -> PathBuf {
     root.to_path_buf()
 }
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+
+    use super::{NodeInstaller, VersionRequest};
+    use crate::settings::tests::serve;
+
+    #[tokio::test]
+    async fn mirror() -> anyhow::Result<()> {
+        let index = r#"[
+            {"version": "v22.1.0", "lts": false},
+            {"version": "v20.12.2", "lts": "Iron"}
+        ]"#;
+        let dist = serve(vec![(
+            "/dist/index.json".to_string(),
+            index.as_bytes().to_vec(),
+        )]);
+        let root = tempfile::tempdir()?;
+        let installer = NodeInstaller {
+            root: root.path().to_path_buf(),
+            client: Client::new(),
+            dist: format!("{dist}/dist"),
+        };
+
+        let version = installer.resolve_version(&"iron".parse()?).await?;
+        assert_eq!(version.to_string(), "20.12.2-Iron");
+        let version = installer.resolve_version(&VersionRequest::Any).await?;
+        assert_eq!(version.to_string(), "22.1.0");
+
+        Ok(())
+    }
+}
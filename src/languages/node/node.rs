@@ -1,15 +1,48 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 
+use crate::config::{LanguageVersion, LockedEnv};
 use crate::hook::Hook;
 use crate::languages::LanguageImpl;
 use crate::languages::node::installer::NodeInstaller;
+use crate::run::OutputStream;
 use crate::store::{Store, ToolBucket};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Node;
 
+impl Node {
+    /// Resolve the Node.js version of a hook, dependencies are not installed yet.
+    pub(crate) async fn resolve(&self, hook: &Hook) -> Result<LockedEnv> {
+        let store = Store::from_settings()?;
+        let installer = NodeInstaller::new(store.tools_path(ToolBucket::Node))?;
+        let version = installer.resolve(&hook.language_version).await?;
+
+        Ok(LockedEnv {
+            resolved_version: version.to_string(),
+            resolved_dependencies: Vec::new(),
+        })
+    }
+
+    /// The Node.js installation in the store the hook uses, if any.
+    pub(crate) async fn toolchain(&self, hook: &Hook) -> Result<Option<PathBuf>> {
+        let store = Store::from_settings()?;
+        let installer = NodeInstaller::new(store.tools_path(ToolBucket::Node))?;
+        let node = installer.install(&Self::version(hook)).await?;
+        Ok(node.dir().map(Path::to_path_buf))
+    }
+
+    /// The locked version of the hook, or the requested one.
+    fn version(hook: &Hook) -> LanguageVersion {
+        match &hook.lock {
+            Some(lock) => LanguageVersion::Specific(lock.resolved_version.clone()),
+            None => hook.language_version.clone(),
+        }
+    }
+}
+
 impl LanguageImpl for Node {
     fn supports_dependency(&self) -> bool {
         true
@@ -22,8 +55,8 @@ impl LanguageImpl for Node {
         let store = Store::from_settings()?;
         let node_dir = store.tools_path(ToolBucket::Node);
 
-        let installer = NodeInstaller::new(node_dir);
-        let node = installer.install(&hook.language_version).await?;
+        let installer = NodeInstaller::new(node_dir)?;
+        let node = installer.install(&Self::version(hook)).await?;
 
         // TODO: Create an env
         _ = node;
@@ -31,8 +64,9 @@ impl LanguageImpl for Node {
         Ok(())
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    async fn check_health(&self, _hook: &Hook) -> Result<()> {
+        // No env is created yet, see `install`.
+        Ok(())
     }
 
     async fn run(
@@ -40,6 +74,7 @@ impl LanguageImpl for Node {
         _hook: &Hook,
         _filenames: &[&String],
         _env_vars: &HashMap<&'static str, String>,
+        _stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)> {
         Ok((0, Vec::new()))
     }
@@ -3,3 +3,4 @@ mod python;
 mod uv;
 
 pub use python::Python;
+pub(crate) use uv::{UV_MIN_VERSION, UvInstaller};
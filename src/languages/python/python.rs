@@ -6,17 +6,159 @@ use tracing::debug;
 
 use constants::env_vars::EnvVars;
 
-use crate::config::LanguageVersion;
+use crate::config::{LanguageVersion, LockedEnv};
 use crate::hook::Hook;
 use crate::languages::LanguageImpl;
 use crate::languages::python::uv::UvInstaller;
 use crate::process::Cmd;
-use crate::run::run_by_batch;
-use crate::store::{Store, ToolBucket};
+use crate::run::{OutputStream, run_batch, run_by_batch};
+use crate::settings::Settings;
+use crate::store::{Store, ToolBucket, is_offline};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Python;
 
+/// A `uv` command with the hook env, the store for Pythons and the network settings.
+fn uv_cmd(uv: &Path, hook: &Hook, summary: &'static str) -> Cmd {
+    let settings = Settings::get();
+    let mut cmd = Cmd::new(uv, summary);
+    cmd.envs(&hook.env).envs(settings.proxy_envs());
+    if let Ok(store) = Store::from_settings() {
+        cmd.env(
+            EnvVars::UV_PYTHON_INSTALL_DIR,
+            store.tools_path(ToolBucket::Python),
+        );
+    }
+    if let Some(mirror) = &settings.mirrors.python {
+        cmd.env(EnvVars::UV_PYTHON_INSTALL_MIRROR, mirror);
+    }
+    if is_offline() {
+        // Only use the Pythons and packages already installed or cached.
+        cmd.env(EnvVars::UV_OFFLINE, "1");
+    }
+    cmd
+}
+
+/// Add the Python request of `language_version`, or the `locked` version, to a `uv venv` or
+/// `uv python` command.
+fn python_request(cmd: &mut Cmd, language_version: &LanguageVersion, locked: Option<&str>) {
+    let version = match language_version {
+        LanguageVersion::Specific(version) => Some(version.as_str()),
+        // uv will try to use system Python and download if not found
+        LanguageVersion::System | LanguageVersion::Default => None,
+    };
+    if let Some(version) = locked.or(version) {
+        cmd.arg("--python").arg(version);
+    }
+    // A locked version of the system Python must not be downloaded either.
+    if !language_version.allows_download() {
+        cmd.arg("--python-preference").arg("only-system");
+    }
+}
+
+/// The pinned requirements in `uv pip compile` output, without the hook repo itself,
+/// which is installed from its path.
+fn pinned_requirements(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| !line.contains(" @ file://"))
+        .map(ToString::to_string)
+        .collect()
+}
+
+impl Python {
+    /// The Python version of an installed environment, from its `pyvenv.cfg`.
+    pub(crate) fn installed_version(venv: &Path) -> Option<String> {
+        let cfg = fs_err::read_to_string(venv.join("pyvenv.cfg")).ok()?;
+        pyvenv_version(&cfg)
+    }
+
+    /// The Python installation in the store a venv was created from, if any.
+    pub(crate) fn toolchain(venv: &Path) -> Option<PathBuf> {
+        let cfg = fs_err::read_to_string(venv.join("pyvenv.cfg")).ok()?;
+        let home = pyvenv_value(&cfg, "home").map(PathBuf::from)?;
+
+        let pythons = Store::from_settings().ok()?.tools_path(ToolBucket::Python);
+        let version = home.strip_prefix(&pythons).ok()?.components().next()?;
+        Some(pythons.join(version))
+    }
+
+    /// Resolve the Python version and pin the dependencies of a hook with `uv pip compile`.
+    pub(crate) async fn resolve(&self, hook: &Hook) -> Result<LockedEnv> {
+        let uv = UvInstaller::install().await?;
+
+        // Find the Python `uv venv` would use, installing it if it is not found.
+        let find = || {
+            let mut cmd = uv_cmd(&uv, hook, "find python");
+            cmd.arg("python").arg("find");
+            python_request(&mut cmd, &hook.language_version, None);
+            cmd
+        };
+        let output = find().check(false).output().await?;
+        let output = if output.status.success() {
+            output
+        } else if hook.language_version.allows_download() {
+            let mut install = uv_cmd(&uv, hook, "install python");
+            install.arg("python").arg("install");
+            if let LanguageVersion::Specific(version) = &hook.language_version {
+                install.arg(version);
+            }
+            install.check(true).output().await?;
+            find().check(true).output().await?
+        } else {
+            anyhow::bail!(
+                "No system Python found for hook `{}`: {}",
+                hook.id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        };
+        let python = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+
+        let output = Cmd::new(&python, "get python version")
+            .arg("-c")
+            .arg("import platform; print(platform.python_version())")
+            .check(true)
+            .output()
+            .await?;
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let temp = tempfile::tempdir()?;
+        let requirements = temp.path().join("requirements.in");
+        let mut inputs = Vec::new();
+        if let Some(repo_path) = hook.repo_path() {
+            inputs.push(repo_path.to_string_lossy().to_string());
+        }
+        inputs.extend(hook.additional_dependencies.iter().cloned());
+        let dependencies = if inputs.is_empty() {
+            Vec::new()
+        } else {
+            fs_err::tokio::write(&requirements, inputs.join("\n")).await?;
+            let output = uv_cmd(&uv, hook, "resolve dependencies")
+                .arg("pip")
+                .arg("compile")
+                .arg(&requirements)
+                .arg("--python")
+                .arg(&python)
+                .arg("--no-header")
+                .arg("--no-annotate")
+                // Keep the platform specific dependencies of all platforms, with their markers,
+                // so the lockfile works on other machines.
+                .arg("--universal")
+                .check(true)
+                .output()
+                .await?;
+            pinned_requirements(&String::from_utf8_lossy(&output.stdout))
+        };
+
+        Ok(LockedEnv {
+            resolved_version: version,
+            resolved_dependencies: dependencies,
+        })
+    }
+}
+
 impl LanguageImpl for Python {
     fn supports_dependency(&self) -> bool {
         true
@@ -27,51 +169,52 @@ impl LanguageImpl for Python {
         let venv = hook.env_path().expect("Python must have env path");
 
         let uv = UvInstaller::install().await?;
-
-        let store = Store::from_settings()?;
-        let python_install_dir = store.tools_path(ToolBucket::Python);
-
-        let uv_cmd = |summary| {
-            let mut cmd = Cmd::new(&uv, summary);
-            cmd.env(EnvVars::UV_PYTHON_INSTALL_DIR, &python_install_dir);
-            cmd
-        };
+        let uv_cmd = |summary| uv_cmd(&uv, hook, summary);
 
         // Create venv
         let mut cmd = uv_cmd("create venv");
         cmd.arg("venv").arg(venv);
 
-        match hook.language_version {
-            LanguageVersion::Specific(ref version) => {
-                cmd.arg("--python").arg(version);
-            }
-            LanguageVersion::System => {
-                cmd.arg("--python-preference").arg("only-system");
-            }
-            // uv will try to use system Python and download if not found
-            LanguageVersion::Default => {}
-        }
+        python_request(
+            &mut cmd,
+            &hook.language_version,
+            hook.lock
+                .as_ref()
+                .map(|lock| lock.resolved_version.as_str()),
+        );
 
         cmd.check(true).output().await?;
 
+        // Pin the dependencies to the locked versions.
+        let constraints = match &hook.lock {
+            Some(lock) if !lock.resolved_dependencies.is_empty() => {
+                let constraints = venv.join("constraints.txt");
+                fs_err::tokio::write(&constraints, lock.resolved_dependencies.join("\n")).await?;
+                Some(constraints)
+            }
+            _ => None,
+        };
+        let install_cmd = || {
+            let mut cmd = uv_cmd("install dependencies");
+            cmd.arg("pip").arg("install").env("VIRTUAL_ENV", venv);
+            if let Some(constraints) = &constraints {
+                cmd.arg("--constraint").arg(constraints);
+            }
+            cmd
+        };
+
         // Install dependencies
         if let Some(repo_path) = hook.repo_path() {
-            uv_cmd("install dependencies")
-                .arg("pip")
-                .arg("install")
+            install_cmd()
                 .arg(".")
                 .args(&hook.additional_dependencies)
                 .current_dir(repo_path)
-                .env("VIRTUAL_ENV", venv)
                 .check(true)
                 .output()
                 .await?;
         } else if !hook.additional_dependencies.is_empty() {
-            uv_cmd("install dependencies")
-                .arg("pip")
-                .arg("install")
+            install_cmd()
                 .args(&hook.additional_dependencies)
-                .env("VIRTUAL_ENV", venv)
                 .check(true)
                 .output()
                 .await?;
@@ -81,8 +224,22 @@ impl LanguageImpl for Python {
         Ok(())
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    async fn check_health(&self, hook: &Hook) -> Result<()> {
+        let venv = hook.env_path().expect("Python must have env path");
+        let python = bin_dir(venv)
+            .join("python")
+            .with_extension(std::env::consts::EXE_EXTENSION);
+        if !python.is_file() {
+            anyhow::bail!("`{}` does not exist", python.display());
+        }
+
+        // The interpreter the venv links to may have been removed or upgraded.
+        Cmd::new(&python, "check python version")
+            .arg("--version")
+            .check(true)
+            .output()
+            .await?;
+        Ok(())
     }
 
     async fn run(
@@ -90,6 +247,7 @@ impl LanguageImpl for Python {
         hook: &Hook,
         filenames: &[&String],
         env_vars: &HashMap<&'static str, String>,
+        stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)> {
         // Get environment directory and parse command
         let env_dir = hook.env_path().expect("Python must have env path");
@@ -107,23 +265,19 @@ impl LanguageImpl for Python {
             ),
         )?;
 
-        let run = async move |batch: Vec<String>| {
-            // TODO: combine stdout and stderr
-            let mut output = Cmd::new(&cmds[0], "run python command")
-                .args(&cmds[1..])
+        let run = async move |batch: &[&String]| {
+            let mut cmd = Cmd::new(&cmds[0], "run python command");
+            cmd.args(&cmds[1..])
                 .env("VIRTUAL_ENV", env_dir)
                 .env("PATH", &new_path)
                 .env_remove("PYTHONHOME")
                 .envs(env_vars)
+                .envs(&hook.env)
                 .args(&hook.args)
                 .args(batch)
-                .check(false)
-                .output()
-                .await?;
+                .check(false);
 
-            output.stdout.extend(output.stderr);
-            let code = output.status.code().unwrap_or(1);
-            anyhow::Ok((code, output.stdout))
+            run_batch(&mut cmd, stream).await
         };
 
         let results = run_by_batch(hook, filenames, run).await?;
@@ -141,6 +295,19 @@ impl LanguageImpl for Python {
     }
 }
 
+/// A `key = value` entry of a `pyvenv.cfg`.
+fn pyvenv_value(cfg: &str, key: &str) -> Option<String> {
+    cfg.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().to_string())
+    })
+}
+
+/// The `version_info` (uv) or `version` (virtualenv) recorded in a `pyvenv.cfg`.
+fn pyvenv_version(cfg: &str) -> Option<String> {
+    pyvenv_value(cfg, "version_info").or_else(|| pyvenv_value(cfg, "version"))
+}
+
 fn bin_dir(venv: &Path) -> PathBuf {
     if cfg!(windows) {
         venv.join("Scripts")
@@ -148,3 +315,77 @@ fn bin_dir(venv: &Path) -> PathBuf {
         venv.join("bin")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::LanguageVersion;
+    use crate::process::Cmd;
+
+    use super::{pinned_requirements, python_request, pyvenv_version};
+
+    fn request(language_version: &LanguageVersion, locked: Option<&str>) -> Vec<String> {
+        let mut cmd = Cmd::new("uv", "create venv");
+        python_request(&mut cmd, language_version, locked);
+        cmd.get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn python_requests() {
+        assert!(request(&LanguageVersion::Default, None).is_empty());
+        assert_eq!(
+            request(&LanguageVersion::Specific("3.12".to_string()), None),
+            ["--python", "3.12"]
+        );
+        assert_eq!(
+            request(&LanguageVersion::System, None),
+            ["--python-preference", "only-system"]
+        );
+        assert_eq!(
+            request(&LanguageVersion::Default, Some("3.12.4")),
+            ["--python", "3.12.4"]
+        );
+        assert_eq!(
+            request(&LanguageVersion::System, Some("3.12.4")),
+            ["--python", "3.12.4", "--python-preference", "only-system"]
+        );
+    }
+
+    #[test]
+    fn pyvenv() {
+        let cfg = indoc::indoc! {"
+            home = /usr/bin
+            implementation = CPython
+            uv = 0.6.0
+            version_info = 3.12.4
+            include-system-site-packages = false
+        "};
+        assert_eq!(pyvenv_version(cfg).as_deref(), Some("3.12.4"));
+        assert_eq!(
+            pyvenv_version("home = /usr/bin\nversion = 3.11.9\n").as_deref(),
+            Some("3.11.9")
+        );
+        assert_eq!(pyvenv_version("home = /usr/bin\n"), None);
+    }
+
+    #[test]
+    fn pinned() {
+        let output = indoc::indoc! {"
+            black @ file:///home/user/.cache/prefligit/repos/0f1e2d3c
+            click==8.1.7
+                # via black
+            colorama==0.4.6 ; sys_platform == 'win32'
+                # via click
+            mypy-extensions==1.0.0
+        "};
+        assert_eq!(
+            pinned_requirements(output),
+            [
+                "click==8.1.7",
+                "colorama==0.4.6 ; sys_platform == 'win32'",
+                "mypy-extensions==1.0.0"
+            ]
+        );
+    }
+}
@@ -4,15 +4,25 @@ use std::time::Duration;
 
 use anyhow::Result;
 use axoupdater::{AxoUpdater, ReleaseSource, ReleaseSourceType, UpdateRequest};
+use futures::TryStreamExt;
+use target_lexicon::HOST;
 use tokio::task::JoinSet;
+use tokio_util::compat::FuturesAsyncReadCompatExt;
 use tracing::{debug, enabled, trace, warn};
 
+use crate::archive;
+use crate::archive::ArchiveExtension;
 use crate::fs::LockedFile;
 use crate::process::Cmd;
-use crate::store::{Store, ToolBucket};
+use crate::settings::Settings;
+use crate::store::{Store, ToolBucket, is_offline};
 
 // The version of `uv` to install. Should update periodically.
 const UV_VERSION: &str = "0.6.0";
+// The GitHub releases of `uv`, laid out like the mirrors.
+const GITHUB_RELEASES: &str = "https://github.com/astral-sh/uv/releases/download";
+// The oldest `uv` found in `PATH` to use, older ones may lack the options we pass.
+pub(crate) const UV_MIN_VERSION: semver::Version = semver::Version::new(0, 5, 0);
 
 #[derive(Debug)]
 enum PyPiMirror {
@@ -50,14 +60,22 @@ enum InstallSource {
     PyPi(PyPiMirror),
     /// Install uv by running `pip install uv`.
     Pip,
+    /// Download uv from a mirror of the GitHub releases.
+    Mirror(String),
 }
 
 impl InstallSource {
-    async fn install(&self, target: &Path) -> Result<()> {
+    async fn install(&self, target: &Path, settings: &Settings) -> Result<()> {
         match self {
+            // `axoupdater` can't use the proxy or the CA certificates, download the release directly.
+            Self::GitHub if settings.proxy.is_some() || settings.ca_cert.is_some() => {
+                self.install_from_mirror(target, GITHUB_RELEASES, settings)
+                    .await
+            }
             Self::GitHub => self.install_from_github(target).await,
-            Self::PyPi(source) => self.install_from_pypi(target, source).await,
-            Self::Pip => self.install_from_pip(target).await,
+            Self::PyPi(source) => self.install_from_pypi(target, source, settings).await,
+            Self::Pip => self.install_from_pip(target, settings).await,
+            Self::Mirror(mirror) => self.install_from_mirror(target, mirror, settings).await,
         }
     }
 
@@ -98,27 +116,77 @@ impl InstallSource {
         }
     }
 
-    async fn install_from_pypi(&self, target: &Path, _source: &PyPiMirror) -> Result<()> {
+    async fn install_from_mirror(
+        &self,
+        target: &Path,
+        mirror: &str,
+        settings: &Settings,
+    ) -> Result<()> {
+        let ext = if cfg!(windows) { "zip" } else { "tar.gz" };
+        let filename = format!("uv-{HOST}.{ext}");
+        let url = format!("{}/{UV_VERSION}/{filename}", mirror.trim_end_matches('/'));
+
+        let archive = settings
+            .http_client()?
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes_stream()
+            .map_err(std::io::Error::other)
+            .into_async_read()
+            .compat();
+
+        let temp_dir = tempfile::tempdir_in(target)?;
+        trace!(url = %url, temp_dir = ?temp_dir.path(), "Downloading uv");
+        archive::unpack(
+            archive,
+            ArchiveExtension::from_path(&filename)?,
+            temp_dir.path(),
+        )
+        .await?;
+
+        // The executables are in a `uv-<target>` directory, except in the Windows archive.
+        let extracted = match archive::strip_component(temp_dir.path()) {
+            Ok(top_level) => top_level,
+            Err(archive::Error::NonSingularArchive(_)) => temp_dir.path().to_path_buf(),
+            Err(err) => return Err(err.into()),
+        };
+        let uv = Path::new("uv").with_extension(env::consts::EXE_EXTENSION);
+        fs_err::tokio::rename(extracted.join(&uv), target.join(&uv)).await?;
+
+        Ok(())
+    }
+
+    async fn install_from_pypi(
+        &self,
+        target: &Path,
+        _source: &PyPiMirror,
+        settings: &Settings,
+    ) -> Result<()> {
         // TODO: Implement this, currently just fallback to pip install
         // Determine the host system
         // Get the html page
         // Parse html, get the latest version url
         // Download the tarball
         // Extract the tarball
-        self.install_from_pip(target).await
+        self.install_from_pip(target, settings).await
     }
 
-    async fn install_from_pip(&self, target: &Path) -> Result<()> {
-        Cmd::new("python3", "pip install uv")
-            .arg("-m")
+    async fn install_from_pip(&self, target: &Path, settings: &Settings) -> Result<()> {
+        let mut cmd = Cmd::new("python3", "pip install uv");
+        cmd.arg("-m")
             .arg("pip")
             .arg("install")
             .arg("--prefix")
             .arg(target)
             .arg(format!("uv=={UV_VERSION}"))
-            .check(true)
-            .output()
-            .await?;
+            .envs(settings.proxy_envs());
+        // pip uses its own CA bundle instead of `SSL_CERT_FILE`.
+        if let Some(ca_cert) = &settings.ca_cert {
+            cmd.arg("--cert").arg(ca_cert);
+        }
+        cmd.check(true).output().await?;
 
         let bin_dir = target.join(if cfg!(windows) { "Scripts" } else { "bin" });
         let lib_dir = target.join(if cfg!(windows) { "Lib" } else { "lib" });
@@ -186,7 +254,11 @@ impl UvInstaller {
             Ok(best)
         }
 
-        let client = reqwest::Client::new();
+        if let Some(mirror) = &Settings::get().mirrors.uv {
+            return Ok(InstallSource::Mirror(mirror.clone()));
+        }
+
+        let client = Settings::get().http_client()?;
         let source = tokio::select! {
             Ok(true) = check_github(&client) => InstallSource::GitHub,
             Ok(source) = select_best_pypi(&client) => InstallSource::PyPi(source),
@@ -200,19 +272,46 @@ impl UvInstaller {
         Ok(source)
     }
 
+    /// The version of a `uv` executable.
+    pub async fn version(uv: &Path) -> Result<semver::Version> {
+        let output = Cmd::new(uv, "get uv version")
+            .arg("--version")
+            .check(true)
+            .output()
+            .await?;
+        parse_version(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// The path of the `uv` installed by `prefligit`.
+    pub fn managed_path(store: &Store) -> PathBuf {
+        store
+            .tools_path(ToolBucket::Uv)
+            .join("uv")
+            .with_extension(env::consts::EXE_EXTENSION)
+    }
+
     pub async fn install() -> Result<PathBuf> {
-        // 1) Check if `uv` is installed already.
-        // TODO: check minimum supported uv version
+        // 1) Check if a recent enough `uv` is installed already.
         if let Ok(uv) = which::which("uv") {
-            trace!(uv = %uv.display(), "Found uv from PATH");
-            return Ok(uv);
+            match Self::version(&uv).await {
+                Ok(version) if version >= UV_MIN_VERSION => {
+                    trace!(uv = %uv.display(), %version, "Found uv from PATH");
+                    return Ok(uv);
+                }
+                Ok(version) => {
+                    debug!(uv = %uv.display(), %version, "Ignoring uv from PATH older than {UV_MIN_VERSION}");
+                }
+                Err(err) => {
+                    debug!(uv = %uv.display(), "Ignoring uv from PATH: {err}");
+                }
+            }
         }
 
         // 2) Check if `uv` is installed by `prefligit`
         let store = Store::from_settings()?;
 
         let uv_dir = store.tools_path(ToolBucket::Uv);
-        let uv = uv_dir.join("uv").with_extension(env::consts::EXE_EXTENSION);
+        let uv = Self::managed_path(&store);
         if uv.is_file() {
             trace!(uv = %uv.display(), "Found managed uv");
             return Ok(uv);
@@ -226,9 +325,128 @@ impl UvInstaller {
             return Ok(uv);
         }
 
+        if is_offline() {
+            anyhow::bail!("uv is not found, and cannot be downloaded in offline mode");
+        }
+
         let source = Self::select_source().await?;
-        source.install(&uv_dir).await?;
+        source.install(&uv_dir, Settings::get()).await?;
 
         Ok(uv)
     }
 }
+
+/// Parse the output of `uv --version`, e.g. `uv 0.6.0 (4d1c0bd8f 2025-02-13)`.
+fn parse_version(stdout: &str) -> Result<semver::Version> {
+    let version = stdout
+        .split_whitespace()
+        .nth(1)
+        .ok_or_else(|| anyhow::anyhow!("Unexpected `uv --version` output: {stdout}"))?;
+    Ok(semver::Version::parse(version)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use target_lexicon::HOST;
+
+    use super::{InstallSource, UV_MIN_VERSION, UV_VERSION, parse_version};
+    use crate::archive;
+    use crate::settings::Settings;
+    use crate::settings::tests::serve;
+
+    #[test]
+    fn version() -> anyhow::Result<()> {
+        let version = parse_version("uv 0.6.0 (4d1c0bd8f 2025-02-13)\n")?;
+        assert_eq!(version, semver::Version::new(0, 6, 0));
+        assert!(version >= UV_MIN_VERSION);
+
+        assert!(parse_version("uv 0.5.0")? >= UV_MIN_VERSION);
+        assert!(parse_version("uv 0.4.30 (Homebrew 2024-11-05)")? < UV_MIN_VERSION);
+        assert!(parse_version("uv 0.5.0-rc.1")? < UV_MIN_VERSION);
+
+        assert!(parse_version("uv").is_err());
+        assert!(parse_version("uv main").is_err());
+
+        Ok(())
+    }
+
+    /// A release archive of a fake `uv` for the host.
+    async fn uv_tarball(temp: &std::path::Path) -> anyhow::Result<Vec<u8>> {
+        let uv = temp.join("uv");
+        fs_err::write(&uv, "#!/bin/sh\necho uv")?;
+        let mut tarball = Vec::new();
+        archive::tar_gz(
+            &mut tarball,
+            &[(PathBuf::from(format!("uv-{HOST}/uv")), uv)],
+        )
+        .await?;
+        Ok(tarball)
+    }
+
+    // The Windows archive is a zip file.
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn mirror() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let tarball = uv_tarball(temp.path()).await?;
+
+        let mirror = serve(vec![(format!("/{UV_VERSION}/uv-{HOST}.tar.gz"), tarball)]);
+        let target = temp.path().join("tools");
+        fs_err::create_dir_all(&target)?;
+        InstallSource::Mirror(format!("{mirror}/"))
+            .install(&target, &Settings::default())
+            .await?;
+
+        assert_eq!(
+            fs_err::read_to_string(target.join("uv"))?,
+            "#!/bin/sh\necho uv"
+        );
+
+        Ok(())
+    }
+
+    // The Windows archive is a zip file.
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn proxy() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let tarball = uv_tarball(temp.path()).await?;
+
+        // The host does not exist, the download only succeeds through the proxy.
+        let proxy = serve(vec![(format!("/{UV_VERSION}/uv-{HOST}.tar.gz"), tarball)]);
+        let settings = Settings {
+            proxy: Some(proxy),
+            ..Settings::default()
+        };
+        let target = temp.path().join("tools");
+        fs_err::create_dir_all(&target)?;
+        InstallSource::Mirror("http://uv.invalid/releases/download".to_string())
+            .install(&target, &settings)
+            .await?;
+
+        assert_eq!(
+            fs_err::read_to_string(target.join("uv"))?,
+            "#!/bin/sh\necho uv"
+        );
+
+        // GitHub releases are downloaded with the CA certificates too, instead of by `axoupdater`.
+        let ca_cert = temp.path().join("ca.pem");
+        fs_err::write(&ca_cert, "not a certificate")?;
+        let settings = Settings {
+            ca_cert: Some(ca_cert),
+            ..Settings::default()
+        };
+        let err = InstallSource::GitHub
+            .install(&target, &settings)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Failed to read CA certificates from")
+        );
+
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use anyhow::Result;
 use crate::hook::Hook;
 use crate::languages::LanguageImpl;
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{OutputStream, run_batch, run_by_batch};
 
 #[derive(Debug, Copy, Clone)]
 pub struct System;
@@ -19,7 +19,7 @@ impl LanguageImpl for System {
         Ok(())
     }
 
-    async fn check_health(&self) -> anyhow::Result<()> {
+    async fn check_health(&self, _hook: &Hook) -> anyhow::Result<()> {
         Ok(())
     }
 
@@ -28,22 +28,20 @@ impl LanguageImpl for System {
         hook: &Hook,
         filenames: &[&String],
         env_vars: &HashMap<&'static str, String>,
+        stream: Option<&OutputStream>,
     ) -> Result<(i32, Vec<u8>)> {
         let cmds = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
 
-        let run = async move |batch: Vec<String>| {
-            let mut output = Cmd::new(&cmds[0], "run system command")
-                .args(&cmds[1..])
+        let run = async move |batch: &[&String]| {
+            let mut cmd = Cmd::new(&cmds[0], "run system command");
+            cmd.args(&cmds[1..])
                 .args(&hook.args)
                 .args(batch)
                 .envs(env_vars)
-                .check(false)
-                .output()
-                .await?;
+                .envs(&hook.env)
+                .check(false);
 
-            output.stdout.extend(output.stderr);
-            let code = output.status.code().unwrap_or(1);
-            anyhow::Ok((code, output.stdout))
+            run_batch(&mut cmd, stream).await
         };
 
         let results = run_by_batch(hook, filenames, run).await?;
@@ -12,9 +12,13 @@ use tracing_subscriber::EnvFilter;
 use tracing_subscriber::filter::Directive;
 
 use crate::cleanup::cleanup;
-use crate::cli::{Cli, Command, ExitStatus, SelfCommand, SelfNamespace, SelfUpdateArgs};
+use crate::cli::{
+    BundleCommand, BundleNamespace, CacheCommand, CacheNamespace, Cli, Command, ExitStatus,
+    SelfCommand, SelfNamespace, SelfUpdateArgs, StashCommand, StashNamespace,
+};
 use crate::git::get_root;
 use crate::printer::Printer;
+use crate::settings::Settings;
 
 mod archive;
 mod builtin;
@@ -31,6 +35,7 @@ mod process;
 #[cfg(all(unix, feature = "profiler"))]
 mod profiler;
 mod run;
+mod settings;
 mod store;
 mod version;
 mod warnings;
@@ -90,6 +95,17 @@ fn adjust_relative_paths(cli: &mut Cli, new_cwd: &Path) -> Result<()> {
         }
     }
 
+    if let Some(Command::Explain(ref mut args)) = cli.command {
+        args.file = fs::relative_to(std::path::absolute(&args.file)?, new_cwd)?;
+    }
+
+    if let Some(Command::Bundle(BundleNamespace {
+        command: BundleCommand::Export(ref mut args) | BundleCommand::Import(ref mut args),
+    })) = cli.command
+    {
+        args.file = std::path::absolute(&args.file)?;
+    }
+
     if let Some(Command::Run(ref mut args) | Command::TryRepo(ref mut args)) = cli.command {
         args.files = args
             .files
@@ -134,6 +150,12 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
         warnings::enable();
     }
 
+    if cli.globals.offline {
+        store::enable_offline();
+    }
+
+    Settings::init()?;
+
     if cli.command.is_none() {
         cli.command = Some(Command::Run(Box::new(cli.run_args.clone())));
     }
@@ -179,6 +201,7 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
                 args.install_hooks,
                 args.overwrite,
                 args.allow_missing_config,
+                args.hooks_path,
                 printer,
                 None,
             )
@@ -187,14 +210,22 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
         Command::Uninstall(args) => {
             show_settings!(args);
 
-            cli::uninstall(cli.globals.config, args.hook_types, printer).await
+            cli::uninstall(
+                cli.globals.config,
+                args.hook_types,
+                args.hooks_path,
+                printer,
+            )
+            .await
         }
         Command::Run(args) => {
             show_settings!(args);
 
+            let stream = args.stream();
             cli::run(
                 cli.globals.config,
                 args.hook_id,
+                args.groups,
                 args.hook_stage,
                 args.from_ref,
                 args.to_ref,
@@ -203,10 +234,22 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
                 args.show_diff_on_failure,
                 args.extra,
                 cli.globals.verbose > 0,
+                stream,
+                args.pty,
                 printer,
             )
             .await
         }
+        Command::List(args) => {
+            show_settings!(args);
+
+            cli::list(cli.globals.config, args.hook_stage, args.json, printer).await
+        }
+        Command::Explain(args) => {
+            show_settings!(args);
+
+            cli::explain(cli.globals.config, args.file, printer).await
+        }
         Command::HookImpl(args) => {
             show_settings!(args);
 
@@ -221,6 +264,8 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
             .await
         }
         Command::Clean => cli::clean(printer),
+        Command::Doctor => cli::doctor(cli.globals.config, printer).await,
+        Command::Lock => cli::lock(cli.globals.config, printer).await,
         Command::ValidateConfig(args) => {
             show_settings!(args);
 
@@ -232,6 +277,32 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
             Ok(cli::validate_manifest(args.manifests))
         }
         Command::SampleConfig => Ok(cli::sample_config()),
+        Command::Stash(StashNamespace { command }) => match command {
+            StashCommand::List => cli::stash_list(printer),
+            StashCommand::Show(args) => cli::stash_show(&args.id, printer),
+            StashCommand::Restore(args) => cli::stash_restore(&args.id, printer).await,
+        },
+        Command::Cache(CacheNamespace { command }) => match command {
+            CacheCommand::Dir => cli::cache_dir(printer),
+            CacheCommand::Info => cli::cache_info(printer),
+            CacheCommand::Prune(args) => {
+                show_settings!(args);
+
+                cli::cache_prune(args.older_than, args.repo, args.dry_run, printer).await
+            }
+        },
+        Command::Bundle(BundleNamespace { command }) => match command {
+            BundleCommand::Export(args) => {
+                show_settings!(args);
+
+                cli::bundle_export(cli.globals.config, args.file, printer).await
+            }
+            BundleCommand::Import(args) => {
+                show_settings!(args);
+
+                cli::bundle_import(args.file, printer).await
+            }
+        },
         Command::Self_(SelfNamespace {
             command:
                 SelfCommand::Update(SelfUpdateArgs {
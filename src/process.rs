@@ -24,6 +24,8 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 /// Adapt [axoprocess] to use [`tokio::process::Process`] instead of [`std::process::Command`].
 use std::{
     ffi::OsStr,
@@ -31,9 +33,11 @@ use std::{
     process::{CommandArgs, CommandEnvs, ExitStatus, Stdio},
 };
 
+use futures::StreamExt;
 use miette::Diagnostic;
 use owo_colors::OwoColorize;
 use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tracing::trace;
 
 use crate::git::GIT;
@@ -71,6 +75,7 @@ pub struct Cmd {
     pub inner: tokio::process::Command,
     summary: String,
     check_status: bool,
+    pty: bool,
 }
 
 /// Constructors
@@ -82,6 +87,7 @@ impl Cmd {
             summary: summary.into(),
             inner,
             check_status: true,
+            pty: false,
         }
     }
 }
@@ -112,6 +118,15 @@ impl Cmd {
         self.check_status = checked;
         self
     }
+
+    /// Set whether [`Cmd::output_combined`][] runs the command under a pseudo-terminal,
+    /// so it writes colors and flushes lines as on a terminal.
+    ///
+    /// Only supported on Unix, ignored elsewhere.
+    pub fn pty(&mut self, pty: bool) -> &mut Self {
+        self.pty = pty;
+        self
+    }
 }
 
 /// Execution APIs
@@ -147,6 +162,67 @@ impl Cmd {
         Ok(res)
     }
 
+    /// Like [`Cmd::output`][], but with stdout and stderr combined in the order they are written,
+    /// calling `on_line` with each line as it arrives.
+    ///
+    /// Returns the exit status and the combined output.
+    pub async fn output_combined(
+        &mut self,
+        mut on_line: impl FnMut(&[u8]),
+    ) -> Result<(ExitStatus, Vec<u8>)> {
+        self.log_command();
+        let cmd = self.get_program().to_string_lossy().to_string();
+        let summary = self.summary.clone();
+        let exec_error = |cause| Error::Exec {
+            summary: summary.clone(),
+            cmd: cmd.clone(),
+            cause,
+        };
+
+        let (mut child, mut lines) = self.spawn_combined().map_err(exec_error)?;
+        let mut output = Vec::new();
+        while let Some(line) = lines.next().await {
+            let line = line.map_err(exec_error)?;
+            on_line(&line);
+            output.extend(line);
+        }
+        let status = child.wait().await.map_err(exec_error)?;
+
+        self.maybe_check_status(status)?;
+        Ok((status, output))
+    }
+
+    /// Spawn the command with stdout and stderr written to the same pipe,
+    /// or the same pseudo-terminal if [`Cmd::pty`][] is set.
+    #[cfg(unix)]
+    fn spawn_combined(&mut self) -> std::io::Result<(tokio::process::Child, Lines)> {
+        let (reader, writer) = if self.pty { openpty()? } else { pipe()? };
+        self.inner
+            .stdin(Stdio::null())
+            .stdout(writer.try_clone()?)
+            .stderr(writer);
+        let child = self.inner.spawn();
+        // Drop our copies of the write end, so reading ends once the child exits.
+        self.inner.stdout(Stdio::null()).stderr(Stdio::null());
+
+        let reader = tokio::fs::File::from_std(std::fs::File::from(reader));
+        Ok((child?, lines(reader)))
+    }
+
+    /// Spawn the command with stdout and stderr merged line by line as they arrive.
+    #[cfg(not(unix))]
+    fn spawn_combined(&mut self) -> std::io::Result<(tokio::process::Child, Lines)> {
+        let mut child = self
+            .inner
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdout = lines(child.stdout.take().expect("stdout is piped"));
+        let stderr = lines(child.stderr.take().expect("stderr is piped"));
+        Ok((child, futures::stream::select(stdout, stderr).boxed()))
+    }
+
     /// Equivalent to [`std::process::Command::status`][]
     /// but logged, with the error wrapped, and status checked (by default)
     pub async fn status(&mut self) -> Result<ExitStatus> {
@@ -246,6 +322,11 @@ impl Cmd {
         self.inner.as_std().get_args()
     }
 
+    /// The program followed by its arguments, e.g. to measure the command line.
+    pub fn get_command_line(&self) -> impl Iterator<Item = &OsStr> {
+        std::iter::once(self.get_program()).chain(self.get_args())
+    }
+
     /// Forwards to [`std::process::Command::get_envs`][]
     pub fn get_envs(&self) -> CommandEnvs<'_> {
         self.inner.as_std().get_envs()
@@ -287,6 +368,109 @@ impl Cmd {
     }
 }
 
+type Lines = futures::stream::BoxStream<'static, std::io::Result<Vec<u8>>>;
+
+/// Read `reader` line by line, keeping the line endings.
+fn lines(reader: impl AsyncRead + Send + Unpin + 'static) -> Lines {
+    futures::stream::unfold(Some(BufReader::new(reader)), |reader| async move {
+        let mut reader = reader?;
+        let mut line = Vec::new();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) => None,
+            Ok(_) => Some((Ok(line), Some(reader))),
+            // A pseudo-terminal reports `EIO` once the child has closed its side.
+            #[cfg(unix)]
+            Err(err) if err.raw_os_error() == Some(libc::EIO) => {
+                (!line.is_empty()).then_some((Ok(line), None))
+            }
+            // Stop after the first error.
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+    .boxed()
+}
+
+/// Create a pipe whose ends are not inherited by child processes.
+#[cfg(unix)]
+fn pipe() -> std::io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    {
+        // SAFETY: `fds` has room for the two file descriptors.
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    {
+        // SAFETY: `fds` has room for the two file descriptors.
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        for fd in fds {
+            set_cloexec(fd)?;
+        }
+    }
+
+    // SAFETY: `pipe` returned two new file descriptors that nothing else owns.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+/// Open a pseudo-terminal whose ends are not inherited by child processes,
+/// returning the controller and the terminal side.
+#[cfg(unix)]
+fn openpty() -> std::io::Result<(OwnedFd, OwnedFd)> {
+    let (mut controller, mut terminal) = (0, 0);
+    // SAFETY: the name, attributes and window size are optional.
+    if unsafe {
+        libc::openpty(
+            &mut controller,
+            &mut terminal,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    } != 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: `openpty` returned two new file descriptors that nothing else owns.
+    let (controller, terminal) = unsafe {
+        (
+            OwnedFd::from_raw_fd(controller),
+            OwnedFd::from_raw_fd(terminal),
+        )
+    };
+    set_cloexec(controller.as_raw_fd())?;
+    set_cloexec(terminal.as_raw_fd())?;
+
+    // Keep `\n` as is instead of translating it to `\r\n`.
+    let mut termios = std::mem::MaybeUninit::uninit();
+    // SAFETY: `terminal` is a terminal, and `termios` is initialized when `tcgetattr` succeeds.
+    unsafe {
+        if libc::tcgetattr(terminal.as_raw_fd(), termios.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut termios = termios.assume_init();
+        termios.c_oflag &= !libc::OPOST;
+        if libc::tcsetattr(terminal.as_raw_fd(), libc::TCSANOW, &termios) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok((controller, terminal))
+}
+
+#[cfg(unix)]
+fn set_cloexec(fd: std::os::fd::RawFd) -> std::io::Result<()> {
+    // SAFETY: `fcntl` with `F_SETFD` does not touch memory.
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 /// Returns the number of arguments to skip.
 fn skip_args(cmd: &OsStr, cur: &OsStr, next: Option<&&OsStr>) -> usize {
     if GIT.as_ref().is_ok_and(|git| cmd == git) {
@@ -1,12 +1,18 @@
 use std::cmp::max;
+use std::ffi::OsStr;
+use std::fmt::Write;
 use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use futures::StreamExt;
+use owo_colors::OwoColorize;
 use tracing::trace;
 
 use constants::env_vars::EnvVars;
 
 use crate::hook::Hook;
+use crate::printer::Printer;
+use crate::process::Cmd;
 
 pub static CONCURRENCY: LazyLock<usize> = LazyLock::new(|| {
     if EnvVars::is_set(EnvVars::PREFLIGIT_NO_CONCURRENCY) {
@@ -22,79 +28,146 @@ fn target_concurrency(serial: bool) -> usize {
     if serial { 1 } else { *CONCURRENCY }
 }
 
+/// The bytes each argument takes besides its own length: the NUL terminator
+/// and the `argv` pointer on Unix, the separating space on Windows.
+const ARG_OVERHEAD: usize = if cfg!(unix) {
+    1 + size_of::<usize>()
+} else {
+    1
+};
+
+/// Room for what a hook's command line has besides its entry, args and env: the arguments
+/// languages wrap the entry in, like `docker run` with its mounts, and the entry splitting
+/// into several arguments.
+const HOOK_HEADROOM: usize = 1024;
+
+/// The maximum length of a command line, including the overhead of each argument.
+static MAX_CLI_LENGTH: LazyLock<usize> = LazyLock::new(|| {
+    if let Some(length) = EnvVars::var(EnvVars::PREFLIGIT_INTERNAL__MAX_CLI_LENGTH)
+        .ok()
+        .and_then(|length| length.parse().ok())
+    {
+        return length;
+    }
+    platform_max_cli_length()
+});
+
+#[cfg(unix)]
+fn platform_max_cli_length() -> usize {
+    // SAFETY: `sysconf` is always safe to call.
+    let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    let arg_max = usize::try_from(arg_max).unwrap_or(0);
+
+    // The environment shares the space with the arguments.
+    #[allow(clippy::disallowed_methods)]
+    let env_size: usize = std::env::vars_os()
+        .map(|(key, value)| key.len() + value.len() + 2 + size_of::<usize>())
+        .sum();
+
+    // Leave some headroom like `xargs` does, for the environment hooks add.
+    // `_POSIX_ARG_MAX` is the least any system supports.
+    max(
+        arg_max.saturating_sub(env_size).saturating_sub(2048),
+        1 << 12,
+    )
+}
+
+#[cfg(not(unix))]
+fn platform_max_cli_length() -> usize {
+    (1 << 15) - 2048 // UNICODE_STRING max - headroom
+}
+
 /// Iterator that yields partitions of filenames that fit within the maximum command line length.
-struct Partitions<'a> {
-    hook: &'a Hook,
-    filenames: &'a [&'a String],
-    concurrency: usize,
+pub(crate) struct Partitions<'a, T> {
+    filenames: &'a [T],
     current_index: usize,
     command_length: usize,
     max_per_batch: usize,
     max_cli_length: usize,
 }
 
-// TODO: do a more accurate calculation
-impl<'a> Partitions<'a> {
-    fn new(hook: &'a Hook, filenames: &'a [&'a String], concurrency: usize) -> Self {
-        let max_per_batch = max(4, filenames.len().div_ceil(concurrency));
-        // TODO: subtract the env size
-        let max_cli_length = if cfg!(unix) {
-            1 << 12
-        } else {
-            (1 << 15) - 2048 // UNICODE_STRING max - headroom
-        };
-        let command_length =
-            hook.entry.len() + hook.args.iter().map(String::len).sum::<usize>() + hook.args.len();
-
+impl<'a, T: AsRef<OsStr>> Partitions<'a, T> {
+    /// Split the filenames to append to a command line of `command_length` bytes,
+    /// with at most `max_per_batch` filenames in each partition.
+    pub(crate) fn new(filenames: &'a [T], command_length: usize, max_per_batch: usize) -> Self {
         Self {
-            hook,
             filenames,
-            concurrency,
             current_index: 0,
             command_length,
             max_per_batch,
-            max_cli_length,
+            max_cli_length: *MAX_CLI_LENGTH,
         }
     }
+
+    /// Split the filenames to append to a command line, the program followed by its arguments.
+    pub(crate) fn for_command<'c>(
+        command_line: impl IntoIterator<Item = &'c OsStr>,
+        filenames: &'a [T],
+    ) -> Self {
+        let command_length = command_line
+            .into_iter()
+            .map(|arg| arg.len() + ARG_OVERHEAD)
+            .sum();
+        Self::new(filenames, command_length, usize::MAX)
+    }
+
+    fn for_hook(hook: &Hook, filenames: &'a [T], concurrency: usize) -> Self {
+        let max_per_batch = max(4, filenames.len().div_ceil(concurrency));
+        let args_length: usize = std::iter::once(&hook.entry)
+            .chain(&hook.args)
+            .map(|arg| arg.len() + ARG_OVERHEAD)
+            .sum();
+        // The environment of the hook shares the space with the arguments,
+        // docker hooks pass it as `--env KEY=VALUE` arguments.
+        let env_length: usize = hook
+            .env
+            .iter()
+            .map(|(key, value)| "--env".len() + key.len() + 1 + value.len() + 2 * ARG_OVERHEAD)
+            .sum();
+        Self::new(
+            filenames,
+            args_length + env_length + HOOK_HEADROOM,
+            max_per_batch,
+        )
+    }
 }
 
-impl<'a> Iterator for Partitions<'a> {
-    // TODO: produce slices instead of Vec
-    type Item = Vec<&'a String>;
+impl<'a, T: AsRef<OsStr>> Iterator for Partitions<'a, T> {
+    type Item = &'a [T];
 
     fn next(&mut self) -> Option<Self::Item> {
         // Handle empty filenames case
         if self.filenames.is_empty() && self.current_index == 0 {
             self.current_index = 1;
-            return Some(vec![]);
+            return Some(&[]);
         }
 
         if self.current_index >= self.filenames.len() {
             return None;
         }
 
-        let mut current = Vec::new();
-        let mut current_length = self.command_length + 1;
+        let start = self.current_index;
+        let mut current_length = self.command_length;
 
         while self.current_index < self.filenames.len() {
-            let filename = self.filenames[self.current_index];
-            let length = filename.len() + 1;
+            let length = self.filenames[self.current_index].as_ref().len() + ARG_OVERHEAD;
 
-            if current_length + length > self.max_cli_length || current.len() >= self.max_per_batch
+            if current_length + length > self.max_cli_length
+                || self.current_index - start >= self.max_per_batch
             {
                 break;
             }
 
-            current.push(filename);
             current_length += length;
             self.current_index += 1;
         }
 
-        if current.is_empty() {
-            None
-        } else {
-            Some(current)
+        // A filename longer than the limit still goes in a partition of its own.
+        if self.current_index == start {
+            self.current_index += 1;
         }
+
+        Some(&self.filenames[start..self.current_index])
     }
 }
 
@@ -104,13 +177,13 @@ pub async fn run_by_batch<T, F>(
     run: F,
 ) -> anyhow::Result<Vec<T>>
 where
-    F: AsyncFn(Vec<String>) -> anyhow::Result<T>,
+    F: AsyncFn(&[&String]) -> anyhow::Result<T>,
     T: Send + 'static,
 {
     let concurrency = target_concurrency(hook.require_serial);
 
     // Split files into batches
-    let partitions = Partitions::new(hook, filenames, concurrency);
+    let partitions = Partitions::for_hook(hook, filenames, concurrency);
     trace!(
         total_files = filenames.len(),
         concurrency = concurrency,
@@ -118,12 +191,10 @@ where
         hook.id,
     );
 
+    // `AsyncFn` is not `FnMut`, so the closure is not redundant.
+    #[allow(clippy::redundant_closure)]
     let mut tasks = futures::stream::iter(partitions)
-        .map(|batch| {
-            // TODO: avoid this allocation
-            let batch: Vec<_> = batch.into_iter().map(ToString::to_string).collect();
-            run(batch)
-        })
+        .map(|batch| run(batch))
         .buffered(concurrency);
 
     let mut results = Vec::new();
@@ -133,3 +204,135 @@ where
 
     Ok(results)
 }
+
+/// Whether to run hooks under a pseudo-terminal.
+static PTY: AtomicBool = AtomicBool::new(false);
+
+/// Run hooks under a pseudo-terminal, so they write colors as they would on a terminal.
+pub(crate) fn enable_pty() {
+    PTY.store(true, Ordering::Relaxed);
+}
+
+/// Forwards the output of a hook line by line as it arrives, prefixed with the hook id.
+pub(crate) struct OutputStream {
+    prefix: String,
+    printer: Printer,
+    streamed: AtomicBool,
+}
+
+impl OutputStream {
+    pub(crate) fn new(hook: &Hook, printer: Printer) -> Self {
+        Self {
+            prefix: format!("[{}]", hook.id),
+            printer,
+            streamed: AtomicBool::new(false),
+        }
+    }
+
+    fn write_line(&self, line: &[u8]) {
+        self.streamed.store(true, Ordering::Relaxed);
+        let line = String::from_utf8_lossy(line);
+        // Like `print!`, there is nowhere to report a failed write to stdout.
+        let _ = writeln!(
+            self.printer.stdout(),
+            "{} {}",
+            self.prefix.dimmed(),
+            line.trim_end_matches(['\n', '\r'])
+        );
+    }
+
+    /// Whether any output has been forwarded.
+    pub(crate) fn streamed(&self) -> bool {
+        self.streamed.load(Ordering::Relaxed)
+    }
+}
+
+/// Run a batch of a hook with stdout and stderr combined in order,
+/// forwarding each line to `stream` as it arrives.
+pub(crate) async fn run_batch(
+    cmd: &mut Cmd,
+    stream: Option<&OutputStream>,
+) -> anyhow::Result<(i32, Vec<u8>)> {
+    let (status, output) = cmd
+        .pty(PTY.load(Ordering::Relaxed))
+        .output_combined(|line| {
+            if let Some(stream) = stream {
+                stream.write_line(line);
+            }
+        })
+        .await?;
+    Ok((status.code().unwrap_or(1), output))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::process::Cmd;
+
+    use super::Partitions;
+
+    fn filenames(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| format!("src/some/nested/directory/file_{i}.py"))
+            .collect()
+    }
+
+    #[test]
+    fn partitions_huge_list() {
+        let filenames = filenames(100_000);
+        let max_cli_length = 1 << 17;
+        let partitions: Vec<_> = Partitions {
+            max_cli_length,
+            ..Partitions::new(&filenames, 100, usize::MAX)
+        }
+        .collect();
+
+        assert!(partitions.len() > 1);
+        for partition in &partitions {
+            let length = 100
+                + partition
+                    .iter()
+                    .map(|f| f.len() + super::ARG_OVERHEAD)
+                    .sum::<usize>();
+            assert!(length <= max_cli_length);
+        }
+        // All filenames are kept, in order.
+        assert_eq!(partitions.concat(), filenames);
+    }
+
+    #[test]
+    fn partitions_max_per_batch() {
+        let filenames = filenames(10);
+        let sizes: Vec<_> = Partitions::new(&filenames, 0, 4)
+            .map(<[String]>::len)
+            .collect();
+        assert_eq!(sizes, vec![4, 4, 2]);
+    }
+
+    #[test]
+    fn partitions_long_filename() {
+        let filenames = vec!["a".to_string(), "b".repeat(1 << 16), "c".to_string()];
+        let partitions: Vec<_> = Partitions {
+            max_cli_length: 1 << 12,
+            ..Partitions::new(&filenames, 0, usize::MAX)
+        }
+        .collect();
+        assert_eq!(partitions.len(), 3);
+    }
+
+    #[test]
+    fn partitions_for_command() {
+        let mut cmd = Cmd::new("git", "test");
+        cmd.arg("add").arg("--");
+        let partitions = Partitions::<String>::for_command(cmd.get_command_line(), &[]);
+        assert_eq!(
+            partitions.command_length,
+            "git".len() + "add".len() + "--".len() + 3 * super::ARG_OVERHEAD
+        );
+    }
+
+    #[test]
+    fn partitions_empty() {
+        let partitions: Vec<_> = Partitions::<String>::new(&[], 0, usize::MAX).collect();
+        assert_eq!(partitions, vec![&[] as &[String]]);
+    }
+}
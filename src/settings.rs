@@ -0,0 +1,340 @@
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use etcetera::BaseStrategy;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::debug;
+
+use constants::env_vars::EnvVars;
+
+use crate::fs::Simplified;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Failed to read settings file `{}`", .0.user_display())]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("Invalid settings file `{}`", .0.user_display())]
+    Parse(PathBuf, #[source] serde_yaml::Error),
+    #[error("Invalid `{0}`: expected space separated `<prefix>=<replacement>` pairs, got `{1}`")]
+    RepoMirrors(&'static str, String),
+    #[error("Failed to read CA certificates from `{}`", .0.user_display())]
+    CaCert(PathBuf, #[source] anyhow::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+/// Rewrite repo URLs starting with `prefix`, like git's `url.<base>.insteadOf`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RepoMirror {
+    pub(crate) prefix: String,
+    pub(crate) replacement: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Mirrors {
+    #[serde(default)]
+    pub(crate) repos: Vec<RepoMirror>,
+    /// Replaces `https://nodejs.org/dist`.
+    pub(crate) node: Option<String>,
+    /// Replaces `https://github.com/astral-sh/python-build-standalone/releases/download`,
+    /// passed to uv as `UV_PYTHON_INSTALL_MIRROR`.
+    pub(crate) python: Option<String>,
+    /// Replaces `https://github.com/astral-sh/uv/releases/download`.
+    pub(crate) uv: Option<String>,
+}
+
+/// User-level settings for networks that need mirrors, a proxy or a custom CA.
+///
+/// Read from `PREFLIGIT_SETTINGS`, or `settings.yaml` in the `prefligit` config directory,
+/// and overridden by the `PREFLIGIT_*` environment variables.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Settings {
+    #[serde(default)]
+    pub(crate) mirrors: Mirrors,
+    /// The proxy for all HTTP(S) requests and git clones.
+    pub(crate) proxy: Option<String>,
+    /// Comma separated hosts to connect to without the proxy.
+    pub(crate) no_proxy: Option<String>,
+    /// A PEM file of CA certificates to trust, e.g. of a TLS-intercepting proxy.
+    pub(crate) ca_cert: Option<PathBuf>,
+    /// Reject remote repos whose `rev` is not a full commit SHA, since tags and branches can move.
+    #[serde(default)]
+    pub(crate) strict_revs: bool,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+impl Settings {
+    /// Load the settings, must be called before any other access.
+    pub(crate) fn init() -> Result<(), Error> {
+        let settings = Self::load()?;
+        debug!(?settings, "Loaded settings");
+        SETTINGS
+            .set(settings)
+            .expect("Settings must be initialized only once");
+        Ok(())
+    }
+
+    /// The loaded settings, or the defaults if not initialized, e.g. in unit tests.
+    pub(crate) fn get() -> &'static Settings {
+        SETTINGS.get_or_init(Settings::default)
+    }
+
+    fn file() -> Option<PathBuf> {
+        if let Some(path) = EnvVars::var_os(EnvVars::PREFLIGIT_SETTINGS) {
+            return Some(path.into());
+        }
+        etcetera::choose_base_strategy()
+            .map(|path| path.config_dir().join("prefligit").join("settings.yaml"))
+            .ok()
+    }
+
+    fn load() -> Result<Self, Error> {
+        let mut settings = match Self::file() {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+
+        if let Ok(mirrors) = EnvVars::var(EnvVars::PREFLIGIT_REPO_MIRRORS) {
+            settings.mirrors.repos = parse_repo_mirrors(&mirrors)
+                .ok_or(Error::RepoMirrors(EnvVars::PREFLIGIT_REPO_MIRRORS, mirrors))?;
+        }
+        let overrides = [
+            (EnvVars::PREFLIGIT_NODE_MIRROR, &mut settings.mirrors.node),
+            (
+                EnvVars::PREFLIGIT_PYTHON_MIRROR,
+                &mut settings.mirrors.python,
+            ),
+            (EnvVars::PREFLIGIT_UV_MIRROR, &mut settings.mirrors.uv),
+            (EnvVars::PREFLIGIT_PROXY, &mut settings.proxy),
+            (EnvVars::PREFLIGIT_NO_PROXY, &mut settings.no_proxy),
+        ];
+        for (name, value) in overrides {
+            if let Ok(var) = EnvVars::var(name) {
+                *value = Some(var);
+            }
+        }
+        if let Some(ca_cert) = EnvVars::var_os(EnvVars::PREFLIGIT_CA_CERT) {
+            settings.ca_cert = Some(ca_cert.into());
+        }
+        if EnvVars::is_set(EnvVars::PREFLIGIT_STRICT_REVS) {
+            settings.strict_revs = true;
+        }
+
+        Ok(settings)
+    }
+
+    /// Read a settings file, a missing file means the defaults.
+    fn from_file(path: &Path) -> Result<Self, Error> {
+        let content = match fs_err::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(Error::Read(path.to_path_buf(), err)),
+        };
+        // An empty file is `null` in YAML.
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml::from_str(&content).map_err(|err| Error::Parse(path.to_path_buf(), err))
+    }
+
+    /// Rewrite a repo URL with the longest matching mirror prefix.
+    pub(crate) fn repo_url<'a>(&self, url: &'a str) -> Cow<'a, str> {
+        self.mirrors
+            .repos
+            .iter()
+            .filter(|mirror| url.starts_with(&mirror.prefix))
+            .max_by_key(|mirror| mirror.prefix.len())
+            .map_or(Cow::Borrowed(url), |mirror| {
+                debug!(url, mirror = mirror.replacement, "Rewriting repo URL");
+                Cow::Owned(format!(
+                    "{}{}",
+                    mirror.replacement,
+                    &url[mirror.prefix.len()..]
+                ))
+            })
+    }
+
+    /// An HTTP client using the proxy and CA certificates.
+    pub(crate) fn http_client(&self) -> Result<reqwest::Client, Error> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &self.proxy {
+            let no_proxy = self
+                .no_proxy
+                .as_deref()
+                .and_then(reqwest::NoProxy::from_string);
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?.no_proxy(no_proxy));
+        }
+        if let Some(ca_cert) = &self.ca_cert {
+            let certs = fs_err::read(ca_cert)
+                .map_err(anyhow::Error::from)
+                .and_then(|pem| {
+                    let certs = reqwest::Certificate::from_pem_bundle(&pem)?;
+                    anyhow::ensure!(!certs.is_empty(), "no PEM certificates found");
+                    Ok(certs)
+                })
+                .map_err(|err| Error::CaCert(ca_cert.clone(), err))?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        Ok(builder.build()?)
+    }
+
+    /// The environment variables passing the proxy and CA certificates to tools like uv.
+    pub(crate) fn proxy_envs(&self) -> Vec<(&'static str, OsString)> {
+        let mut envs = Vec::new();
+        if let Some(proxy) = &self.proxy {
+            envs.push((EnvVars::HTTP_PROXY, proxy.into()));
+            envs.push((EnvVars::HTTPS_PROXY, proxy.into()));
+        }
+        if let Some(no_proxy) = &self.no_proxy {
+            envs.push((EnvVars::NO_PROXY, no_proxy.into()));
+        }
+        if let Some(ca_cert) = &self.ca_cert {
+            envs.push((EnvVars::SSL_CERT_FILE, ca_cert.into()));
+        }
+        envs
+    }
+}
+
+/// Parse `<prefix>=<replacement>` pairs separated by whitespace.
+fn parse_repo_mirrors(value: &str) -> Option<Vec<RepoMirror>> {
+    value
+        .split_whitespace()
+        .map(|pair| {
+            let (prefix, replacement) = pair.split_once('=')?;
+            Some(RepoMirror {
+                prefix: prefix.to_string(),
+                replacement: replacement.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    use super::{Mirrors, Settings, parse_repo_mirrors};
+
+    /// Serve files over HTTP on a local port, as a stand-in for download mirrors and proxies.
+    ///
+    /// Requests are matched by the end of their path, so absolute URLs sent to a proxy match too.
+    pub(crate) fn serve(files: Vec<(String, Vec<u8>)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                // Skip the headers.
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let response = match files.iter().find(|(name, _)| path.ends_with(name.as_str())) {
+                    Some((_, body)) => {
+                        let mut response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(body);
+                        response
+                    }
+                    None => {
+                        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_vec()
+                    }
+                };
+                stream.write_all(&response).ok();
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn repo_url() {
+        let settings = Settings {
+            mirrors: Mirrors {
+                repos: parse_repo_mirrors(
+                    "https://github.com/=https://git.corp/github/ https://github.com/org/=https://git.corp/org/",
+                )
+                .unwrap(),
+                ..Mirrors::default()
+            },
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            settings.repo_url("https://github.com/pre-commit/pre-commit-hooks"),
+            "https://git.corp/github/pre-commit/pre-commit-hooks"
+        );
+        assert_eq!(
+            settings.repo_url("https://github.com/org/hooks"),
+            "https://git.corp/org/hooks"
+        );
+        assert_eq!(
+            settings.repo_url("https://gitlab.com/org/hooks"),
+            "https://gitlab.com/org/hooks"
+        );
+        assert!(parse_repo_mirrors("https://github.com/").is_none());
+    }
+
+    #[tokio::test]
+    async fn proxy() -> anyhow::Result<()> {
+        let proxy = serve(vec![("/index.json".to_string(), b"[]".to_vec())]);
+        let settings = Settings {
+            proxy: Some(proxy),
+            ..Settings::default()
+        };
+
+        // The host does not exist, the request only succeeds through the proxy.
+        let body = settings
+            .http_client()?
+            .get("http://nodejs.invalid/dist/index.json")
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        assert_eq!(body, "[]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn ca_cert() {
+        let temp = tempfile::tempdir().unwrap();
+        let ca_cert = temp.path().join("ca.pem");
+        fs_err::write(&ca_cert, "not a certificate").unwrap();
+
+        let settings = Settings {
+            ca_cert: Some(ca_cert),
+            ..Settings::default()
+        };
+        let err = settings.http_client().unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Failed to read CA certificates")
+        );
+        assert_eq!(
+            anyhow::Error::from(err).root_cause().to_string(),
+            "no PEM certificates found"
+        );
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::HashSet;
+use std::fmt::Display;
 use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use anyhow::Result;
 use etcetera::BaseStrategy;
@@ -11,10 +15,13 @@ use tracing::debug;
 
 use constants::env_vars::EnvVars;
 
+use crate::builtin;
 use crate::config::RemoteRepo;
 use crate::fs::LockedFile;
-use crate::git::clone_repo;
+use crate::git::{self, RevKind};
 use crate::hook::Hook;
+use crate::settings::Settings;
+use crate::warn_user;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -28,6 +35,25 @@ pub enum Error {
     Repo(#[from] crate::hook::Error),
     #[error(transparent)]
     Git(#[from] crate::git::Error),
+    #[error("Cannot clone repo `{0}` in offline mode")]
+    Offline(String),
+    #[error(
+        "Repo `{0}` is not pinned to a full commit SHA, which strict revs require\nhint: pin `rev` to a commit, or lock it with `prefligit lock`"
+    )]
+    MutableRev(String),
+}
+
+/// Whether to fail instead of accessing the network.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Fail instead of cloning repos or downloading tools, for machines without network access.
+pub(crate) fn enable_offline() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+/// Whether `--offline` or `PREFLIGIT_OFFLINE` is set.
+pub(crate) fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed) || EnvVars::is_set(EnvVars::PREFLIGIT_OFFLINE)
 }
 
 static STORE_HOME: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
@@ -44,6 +70,46 @@ static STORE_HOME: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
     }
 });
 
+/// The file in a cloned repo or a hook environment whose modified time is when it was last used.
+pub const LAST_USED_FILE: &str = ".last_used";
+
+/// The file in a cloned repo recording what its `rev` resolved to, its modified time is when it
+/// was last checked against the remote.
+const RESOLVED_REV_FILE: &str = ".resolved_rev";
+
+/// The file in a cloned tag or branch recording the commit it moved to, which is cloned by
+/// itself so processes using the old clone are not disturbed.
+const MOVED_TO_FILE: &str = ".moved_to";
+
+/// How often the commit of a cloned tag or branch is checked against the remote.
+const REVALIDATE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The commit a cloned repo `rev` resolved to, and what kind of ref `rev` is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRev {
+    pub sha: String,
+    pub kind: RevKind,
+}
+
+impl ResolvedRev {
+    /// Read the resolved rev of a cloned repo, repos cloned by older versions have none.
+    pub fn read(repo_path: &Path) -> Option<Self> {
+        let content = fs_err::read_to_string(repo_path.join(RESOLVED_REV_FILE)).ok()?;
+        let (kind, sha) = content.trim().split_once(' ')?;
+        Some(Self {
+            sha: sha.to_string(),
+            kind: kind.parse().ok()?,
+        })
+    }
+
+    fn write(&self, repo_path: &Path) -> Result<(), std::io::Error> {
+        fs_err::write(
+            repo_path.join(RESOLVED_REV_FILE),
+            format!("{} {}", self.kind, self.sha),
+        )
+    }
+}
+
 /// A store for managing repos.
 #[derive(Debug)]
 pub struct Store {
@@ -81,14 +147,90 @@ impl Store {
     }
 
     /// Clone a remote repo into the store.
+    ///
+    /// Once a cloned tag or branch points to another commit on the remote, that commit is cloned
+    /// next to it.
     pub async fn clone_repo(&self, repo: &RemoteRepo) -> Result<PathBuf, Error> {
+        if Settings::get().strict_revs && !git::is_full_sha(&repo.rev) {
+            return Err(Error::MutableRev(repo.to_string()));
+        }
+
+        let target = self.clone_or_reuse(repo).await?;
+        let resolved = ResolvedRev::read(&self.repo_path(repo));
+        if resolved.is_some_and(|resolved| resolved.kind == RevKind::Branch) {
+            warn_user!(
+                "Repo `{repo}` uses branch `{}` as `rev`, which moves with every push, pin it to a tag or a commit",
+                repo.rev
+            );
+        }
+        Ok(target)
+    }
+
+    /// The clone of a remote repo in use, without cloning it or checking it against the remote.
+    pub fn cloned_repo(&self, repo: &RemoteRepo) -> Result<Option<PathBuf>, Error> {
+        let target = self.repo_path(repo);
+        if !target.join(".cloned_ok").try_exists()? {
+            return Ok(None);
+        }
+        match fs_err::read_to_string(target.join(MOVED_TO_FILE)) {
+            Ok(sha) => self.cloned_repo(&RemoteRepo {
+                rev: sha.trim().to_string(),
+                ..repo.clone()
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Some(target)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn clone_or_reuse(&self, repo: &RemoteRepo) -> Result<PathBuf, Error> {
         // Check if the repo is already cloned.
         let target = self.repo_path(repo);
         if target.join(".cloned_ok").try_exists()? {
-            return Ok(target);
+            if let Some(sha) = Self::revalidate(&target, repo).await? {
+                if ResolvedRev::read(&target).is_some_and(|resolved| resolved.sha == sha) {
+                    // Moved back to the cloned commit.
+                    fs_err::tokio::remove_file(target.join(MOVED_TO_FILE)).await?;
+                } else {
+                    fs_err::tokio::write(target.join(MOVED_TO_FILE), &sha).await?;
+                }
+            }
+            return match fs_err::read_to_string(target.join(MOVED_TO_FILE)) {
+                Ok(sha) => {
+                    let moved = RemoteRepo {
+                        rev: sha.trim().to_string(),
+                        ..repo.clone()
+                    };
+                    Box::pin(self.clone_or_reuse(&moved)).await
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(target),
+                Err(err) => Err(err.into()),
+            };
         }
 
         fs_err::tokio::create_dir_all(self.repos_dir()).await?;
+        let _lock = Self::lock_entry(&target, format!("repo `{repo}`")).await?;
+
+        // Another process may have cloned it while we were waiting for the lock.
+        if target.join(".cloned_ok").try_exists()? {
+            return Ok(target);
+        }
+
+        if is_offline() {
+            return Err(Error::Offline(repo.to_string()));
+        }
+
+        let url = Settings::get().repo_url(repo.repo.as_str());
+        let kind = if git::is_full_sha(&repo.rev) {
+            RevKind::Sha
+        } else {
+            match git::resolve_rev(&url, &repo.rev).await {
+                Ok(resolved) => resolved.map_or(RevKind::Unknown, |(_, kind)| kind),
+                Err(err) => {
+                    debug!(%repo, %err, "Failed to resolve rev");
+                    RevKind::Unresolved
+                }
+            }
+        };
 
         // Clone and checkout the repo.
         let temp = tempfile::tempdir_in(self.repos_dir())?;
@@ -97,18 +239,139 @@ impl Store {
             %repo,
             "Cloning repo",
         );
-        clone_repo(repo.repo.as_str(), &repo.rev, temp.path()).await?;
+        git::clone_repo(&url, &repo.rev, temp.path()).await?;
+        let sha = git::head_commit(temp.path()).await?;
 
         // TODO: add windows retry
         fs_err::tokio::remove_dir_all(&target).await.ok();
         fs_err::tokio::rename(temp, &target).await?;
         fs_err::tokio::write(target.join(".repo_source"), repo.to_string()).await?;
+        ResolvedRev { sha, kind }.write(&target)?;
         fs_err::tokio::write(target.join(".cloned_ok"), "").await?;
 
         Ok(target)
     }
 
-    /// Lock the store.
+    /// Check whether the tag or branch of a cloned repo still points to the commit in use,
+    /// returning the commit it moved to.
+    ///
+    /// Only checked once per [`REVALIDATE_INTERVAL`], and never in offline mode.
+    async fn revalidate(target: &Path, repo: &RemoteRepo) -> Result<Option<String>, Error> {
+        if is_offline() {
+            return Ok(None);
+        }
+        let Some(resolved) = ResolvedRev::read(target) else {
+            return Self::classify(target, repo).await;
+        };
+        if !matches!(
+            resolved.kind,
+            RevKind::Tag | RevKind::Branch | RevKind::Unresolved
+        ) {
+            return Ok(None);
+        }
+        let checked = fs_err::metadata(target.join(RESOLVED_REV_FILE))?.modified()?;
+        if checked
+            .elapsed()
+            .is_ok_and(|elapsed| elapsed < REVALIDATE_INTERVAL)
+        {
+            return Ok(None);
+        }
+
+        // Record when it was checked, even if it fails, so an unreachable remote is only tried
+        // once per interval.
+        resolved.write(target)?;
+        let url = Settings::get().repo_url(repo.repo.as_str());
+        let (sha, kind) = match git::resolve_rev(&url, &repo.rev).await {
+            Ok(Some(resolved)) => resolved,
+            // Keep using the clone if the remote is unreachable or the rev is gone.
+            Ok(None) => {
+                if resolved.kind == RevKind::Unresolved {
+                    ResolvedRev {
+                        sha: resolved.sha,
+                        kind: RevKind::Unknown,
+                    }
+                    .write(target)?;
+                }
+                return Ok(None);
+            }
+            Err(err) => {
+                debug!(%repo, %err, "Failed to revalidate rev");
+                return Ok(None);
+            }
+        };
+        if kind != resolved.kind {
+            ResolvedRev {
+                sha: resolved.sha.clone(),
+                kind,
+            }
+            .write(target)?;
+        }
+        let current = fs_err::read_to_string(target.join(MOVED_TO_FILE))
+            .map_or(resolved.sha.clone(), |sha| sha.trim().to_string());
+        if sha == current {
+            return Ok(None);
+        }
+
+        Self::report_moved(repo, kind, &current, &sha);
+        Ok(Some(sha))
+    }
+
+    /// Record what `rev` of a repo cloned by an older version resolves to, returning the commit
+    /// it moved to since.
+    async fn classify(target: &Path, repo: &RemoteRepo) -> Result<Option<String>, Error> {
+        let head = git::head_commit(target).await?;
+        if git::is_full_sha(&repo.rev) {
+            ResolvedRev {
+                sha: head,
+                kind: RevKind::Sha,
+            }
+            .write(target)?;
+            return Ok(None);
+        }
+        let url = Settings::get().repo_url(repo.repo.as_str());
+        let (sha, kind) = match git::resolve_rev(&url, &repo.rev).await {
+            Ok(Some(resolved)) => resolved,
+            Ok(None) => (head.clone(), RevKind::Unknown),
+            // Resolved with the next revalidation if the remote is unreachable, instead of on
+            // every run.
+            Err(err) => {
+                debug!(%repo, %err, "Failed to resolve rev");
+                ResolvedRev {
+                    sha: head,
+                    kind: RevKind::Unresolved,
+                }
+                .write(target)?;
+                return Ok(None);
+            }
+        };
+        debug!(%repo, %kind, "Classified rev of an old clone");
+        ResolvedRev {
+            sha: head.clone(),
+            kind,
+        }
+        .write(target)?;
+        if !matches!(kind, RevKind::Tag | RevKind::Branch) || sha == head {
+            return Ok(None);
+        }
+        Self::report_moved(repo, kind, &head, &sha);
+        Ok(Some(sha))
+    }
+
+    fn report_moved(repo: &RemoteRepo, kind: RevKind, old: &str, new: &str) {
+        if kind == RevKind::Tag {
+            warn_user!(
+                "Tag `{}` of `{}` moved from `{old}` to `{new}`, cloning it again",
+                repo.rev,
+                repo.repo,
+            );
+        } else {
+            debug!(%repo, old, new, "Branch moved, cloning it again");
+        }
+    }
+
+    /// Lock the store, only held briefly to update metadata shared by all the entries.
+    ///
+    /// Cloning a repo or installing a hook environment locks just that entry, see [`Self::lock_entry`].
     pub fn lock(&self) -> Result<LockedFile, std::io::Error> {
         LockedFile::acquire_blocking(self.path.join(".lock"), "store")
     }
@@ -117,8 +380,18 @@ impl Store {
         LockedFile::acquire(self.path.join(".lock"), "store").await
     }
 
+    /// Lock a cloned repo or a hook environment while it is created or removed.
+    ///
+    /// The lock file is next to the entry, so it is kept when the entry is removed.
+    pub async fn lock_entry(
+        path: &Path,
+        resource: impl Display,
+    ) -> Result<LockedFile, std::io::Error> {
+        LockedFile::acquire(path.with_extension("lock"), resource).await
+    }
+
     /// Returns the path to the cloned repo.
-    fn repo_path(&self, repo: &RemoteRepo) -> PathBuf {
+    pub fn repo_path(&self, repo: &RemoteRepo) -> PathBuf {
         let mut hasher = SeaHasher::new();
         repo.hash(&mut hasher);
         let digest = to_hex(hasher.finish());
@@ -135,6 +408,10 @@ impl Store {
         if hook.is_meta() {
             return None;
         }
+        // Hooks implemented in Rust run without an environment.
+        if builtin::check_fast_path(hook) {
+            return None;
+        }
 
         let mut hasher = SeaHasher::new();
         hook.hash(&mut hasher);
@@ -142,6 +419,23 @@ impl Store {
         Some(self.hooks_dir().join(digest))
     }
 
+    /// Record the repos and environments of the hooks as used now, for `prefligit cache prune`.
+    pub fn mark_as_used<'a>(&self, hooks: impl IntoIterator<Item = &'a Hook>) {
+        let mut seen = HashSet::new();
+        let paths = hooks
+            .into_iter()
+            .flat_map(|hook| [hook.repo_path(), hook.env_path()])
+            .flatten()
+            .filter(|path| path.starts_with(&self.path));
+        for path in paths {
+            if seen.insert(path) {
+                if let Err(err) = fs_err::write(path.join(LAST_USED_FILE), "") {
+                    debug!("Failed to mark `{}` as used: {err}", path.display());
+                }
+            }
+        }
+    }
+
     pub fn repos_dir(&self) -> PathBuf {
         self.path.join("repos")
     }
@@ -154,6 +448,16 @@ impl Store {
         self.path.join("patches")
     }
 
+    /// Returns the directory to save the patches of a worktree, identified by its git dir.
+    ///
+    /// Each linked worktree and submodule has its own git dir, so their patches never mix.
+    pub fn worktree_patches_dir(&self, git_dir: &Path) -> PathBuf {
+        let mut hasher = SeaHasher::new();
+        git_dir.hash(&mut hasher);
+        let digest = to_hex(hasher.finish());
+        self.patches_dir().join(digest)
+    }
+
     /// The path to the tool directory in the store.
     pub fn tools_path(&self, tool: ToolBucket) -> PathBuf {
         self.path.join("tools").join(tool.as_str())
@@ -0,0 +1,90 @@
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+fn write_config(context: &TestContext, repo: &str) -> anyhow::Result<()> {
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add("file.txt");
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: {repo}
+            rev: v1.0.0
+            hooks:
+              - id: echo
+    "});
+    Ok(())
+}
+
+/// A bundle exported on one machine makes the hooks run offline on another.
+// `file://` URLs of the hook repo are written for unix paths.
+#[cfg(unix)]
+#[test]
+fn bundle() -> anyhow::Result<()> {
+    let online = TestContext::new();
+    online.init_project();
+    let repo = online.create_hook_repo()?;
+    write_config(&online, &repo)?;
+
+    let offline = TestContext::new();
+    offline.init_project();
+    write_config(&offline, &repo)?;
+
+    let filters: Vec<_> = offline
+        .filters()
+        .into_iter()
+        .chain(online.filters())
+        .chain([(r"\(\d+(\.\d+)? (B|KiB|MiB)\)", "([SIZE])")])
+        .collect();
+
+    let bundle = online.workdir().child("hooks.tar.gz");
+    cmd_snapshot!(filters.clone(), online.bundle().arg("export").arg(&*bundle), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Bundled 1 entries into `hooks.tar.gz` ([SIZE])
+
+    ----- stderr -----
+    "#);
+
+    // Nothing is cloned yet.
+    cmd_snapshot!(filters.clone(), offline.run().arg("--offline").arg("--all-files"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Cannot clone repo `file://[TEMP_DIR]/hook-repo@v1.0.0` in offline mode
+    "#);
+
+    cmd_snapshot!(filters.clone(), offline.bundle().arg("import").arg(&*bundle), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Imported 1 entries into `[HOME]/`, 0 already present
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(filters.clone(), offline.run().arg("--offline").arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    // Importing again keeps the existing entries.
+    cmd_snapshot!(filters.clone(), offline.bundle().arg("import").arg(&*bundle), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Imported 0 entries into `[HOME]/`, 1 already present
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+#[test]
+fn cache() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    let home = context.home_dir();
+    home.child("repos/aaaa/.repo_source")
+        .write_str("https://github.com/example/hooks@v1.0.0")?;
+    home.child("repos/aaaa/.cloned_ok").write_str("")?;
+    home.child("repos/aaaa/hook.py")
+        .write_str(&"x".repeat(2048))?;
+    home.child("repos/bbbb/.repo_source")
+        .write_str("https://github.com/example/hooks-extra@v2.0.0")?;
+    home.child("repos/bbbb/.cloned_ok").write_str("")?;
+    home.child("hooks/cccc/.repo_source")
+        .write_str("https://github.com/example/hooks@v1.0.0")?;
+    home.child("hooks/cccc/.installed_ok").write_str("")?;
+    home.child("hooks/cccc/bin/python").write_str("python")?;
+    home.child("patches/1700000000000-1.patch")
+        .write_str("patch")?;
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"\d+ seconds? ago", "[AGE]")])
+        .collect();
+
+    cmd_snapshot!(filters.clone(), context.cache().arg("dir"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [HOME]/
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(filters.clone(), context.cache().arg("info"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Store `[HOME]/` (2.1 KiB)
+      repos: 2.1 KiB
+      hooks: 45 B
+      patches: 5 B
+      tools/uv: 0 B
+      tools/python: 0 B
+      tools/node: 0 B
+
+    Repos
+      https://github.com/example/hooks@v1.0.0  2.0 KiB  [AGE]
+        [HOME]/repos/aaaa
+      https://github.com/example/hooks-extra@v2.0.0  45 B  [AGE]
+        [HOME]/repos/bbbb
+
+    Hooks
+      https://github.com/example/hooks@v1.0.0  45 B  [AGE]
+        [HOME]/hooks/cccc
+
+    ----- stderr -----
+    "#);
+
+    // A filter is required.
+    cmd_snapshot!(filters.clone(), context.cache().arg("prune"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: the following required arguments were not provided:
+      <--older-than <AGE>|--repo <URL>>
+
+    Usage: prefligit cache prune <--older-than <AGE>|--repo <URL>>
+
+    For more information, try '--help'.
+    "#);
+
+    // Everything was used just now.
+    cmd_snapshot!(filters.clone(), context.cache().arg("prune").arg("--older-than").arg("30d"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Nothing to prune
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(filters.clone(), context.cache().arg("prune").arg("--repo").arg("https://github.com/example/hooks").arg("--dry-run"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Would remove `[HOME]/repos/aaaa` (https://github.com/example/hooks@v1.0.0, 2.0 KiB)
+    Would remove `[HOME]/hooks/cccc` (https://github.com/example/hooks@v1.0.0, 45 B)
+    Would remove 2 entries, 2.1 KiB
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(filters.clone(), context.cache().arg("prune").arg("--repo").arg("https://github.com/example/hooks"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Removed `[HOME]/repos/aaaa` (https://github.com/example/hooks@v1.0.0, 2.0 KiB)
+    Removed `[HOME]/hooks/cccc` (https://github.com/example/hooks@v1.0.0, 45 B)
+    Removed 2 entries, 2.1 KiB
+
+    ----- stderr -----
+    "#);
+
+    assert!(!home.child("repos/aaaa").exists());
+    assert!(!home.child("hooks/cccc").exists());
+    assert!(home.child("repos/bbbb").exists());
+
+    Ok(())
+}
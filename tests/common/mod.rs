@@ -118,15 +118,73 @@ impl TestContext {
         cmd.current_dir(self.workdir());
         cmd.env(EnvVars::PREFLIGIT_HOME, &*self.home_dir);
         cmd.env(EnvVars::PREFLIGIT_INTERNAL__SORT_FILENAMES, "1");
+        self.isolate_settings(&mut cmd);
         cmd
     }
 
+    /// Ignore the settings file and the overrides of the user running the tests.
+    fn isolate_settings(&self, cmd: &mut Command) {
+        cmd.env(
+            EnvVars::PREFLIGIT_SETTINGS,
+            self.temp_dir.child("missing-settings.yaml").path(),
+        );
+        for var in [
+            EnvVars::PREFLIGIT_OFFLINE,
+            EnvVars::PREFLIGIT_REPO_MIRRORS,
+            EnvVars::PREFLIGIT_NODE_MIRROR,
+            EnvVars::PREFLIGIT_PYTHON_MIRROR,
+            EnvVars::PREFLIGIT_UV_MIRROR,
+            EnvVars::PREFLIGIT_PROXY,
+            EnvVars::PREFLIGIT_NO_PROXY,
+            EnvVars::PREFLIGIT_CA_CERT,
+            EnvVars::PREFLIGIT_STRICT_REVS,
+        ] {
+            cmd.env_remove(var);
+        }
+    }
+
     pub fn run(&self) -> Command {
         let mut command = self.command();
         command.arg("run");
         command
     }
 
+    pub fn list(&self) -> Command {
+        let mut command = self.command();
+        command.arg("list");
+        command
+    }
+
+    pub fn explain(&self) -> Command {
+        let mut command = self.command();
+        command.arg("explain");
+        command
+    }
+
+    pub fn doctor(&self) -> Command {
+        let mut command = self.command();
+        command.arg("doctor");
+        command
+    }
+
+    pub fn cache(&self) -> Command {
+        let mut command = self.command();
+        command.arg("cache");
+        command
+    }
+
+    pub fn bundle(&self) -> Command {
+        let mut command = self.command();
+        command.arg("bundle");
+        command
+    }
+
+    pub fn lock(&self) -> Command {
+        let mut command = self.command();
+        command.arg("lock");
+        command
+    }
+
     pub fn clean(&self) -> Command {
         let mut command = self.command();
         command.arg("clean");
@@ -179,6 +237,11 @@ impl TestContext {
         &self.temp_dir
     }
 
+    /// Get the prefligit home directory, where the store is.
+    pub fn home_dir(&self) -> &ChildPath {
+        &self.home_dir
+    }
+
     /// Initialize a sample project for prefligit.
     pub fn init_project(&self) {
         Command::new("git")
@@ -207,6 +270,16 @@ impl TestContext {
             .success();
     }
 
+    /// Create a `git` command in the working directory, installed hooks use the test home.
+    pub fn git(&self) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.current_dir(self.workdir());
+        cmd.env(EnvVars::PREFLIGIT_HOME, &*self.home_dir);
+        cmd.env(EnvVars::PREFLIGIT_INTERNAL__SORT_FILENAMES, "1");
+        self.isolate_settings(&mut cmd);
+        cmd
+    }
+
     /// Run `git add`.
     pub fn git_add(&self, path: impl AsRef<OsStr>) {
         Command::new("git")
@@ -228,6 +301,91 @@ impl TestContext {
             .success();
     }
 
+    /// Create a hook repo with a `system` hook `echo`, tagged `v1.0.0`, and return its `file://` URL.
+    pub fn create_hook_repo(&self) -> anyhow::Result<String> {
+        let repo = self.temp_dir.child("hook-repo");
+        Self::write_hook_manifest(&repo, "echo")?;
+
+        let git = |args: &[&str]| Self::hook_repo_git(&repo, args);
+        git(&["init", "--initial-branch=master"]);
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        git(&["tag", "v1.0.0"]);
+
+        Ok(format!("file://{}", repo.display()))
+    }
+
+    /// Create a local stand-in for `pre-commit/pre-commit-hooks` tagged `rev`, to list in
+    /// `pre_commit_hooks_mirrors`.
+    ///
+    /// Its hooks, given as ids and types, print `python hook` and fail, so they show when the
+    /// Python hook runs instead of the builtin one. Like the real entries, the entry is a single
+    /// command the builtin hooks parse their arguments after.
+    #[cfg(unix)]
+    pub fn create_pre_commit_hooks_repo(
+        &self,
+        rev: &str,
+        hooks: &[(&str, &str)],
+    ) -> anyhow::Result<String> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = self.temp_dir.child("pre-commit-hooks");
+        let entry = repo.child("python-hook");
+        entry.write_str("#!/bin/sh\necho python hook\nexit 1\n")?;
+        fs_err::set_permissions(&entry, std::fs::Permissions::from_mode(0o755))?;
+
+        let mut manifest = String::new();
+        for (id, types) in hooks {
+            manifest.push_str(&indoc::formatdoc! {r"
+                - id: {id}
+                  name: {id}
+                  entry: {entry}
+                  language: system
+                  types: {types}
+            ", entry = entry.display()});
+        }
+        repo.child(".pre-commit-hooks.yaml").write_str(&manifest)?;
+
+        let git = |args: &[&str]| Self::hook_repo_git(&repo, args);
+        git(&["init", "--initial-branch=main"]);
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        git(&["tag", rev]);
+
+        Ok(format!("file://{}", repo.display()))
+    }
+
+    /// Rename the hook of [`Self::create_hook_repo`] in a new commit, and move `v1.0.0` to it.
+    pub fn move_hook_repo_tag(&self, name: &str) -> anyhow::Result<()> {
+        let repo = self.temp_dir.child("hook-repo");
+        Self::write_hook_manifest(&repo, name)?;
+
+        Self::hook_repo_git(&repo, &["commit", "-am", "Rename hook"]);
+        Self::hook_repo_git(&repo, &["tag", "--force", "v1.0.0"]);
+        Ok(())
+    }
+
+    fn write_hook_manifest(repo: &ChildPath, name: &str) -> anyhow::Result<()> {
+        repo.child(".pre-commit-hooks.yaml")
+            .write_str(&indoc::formatdoc! {r"
+            - id: echo
+              name: {name}
+              entry: echo
+              language: system
+        "})?;
+        Ok(())
+    }
+
+    fn hook_repo_git(repo: &ChildPath, args: &[&str]) {
+        Command::new("git")
+            .args(["-c", "user.name=Prefligit Test"])
+            .args(["-c", "user.email=test@prefligit.dev"])
+            .args(args)
+            .current_dir(repo)
+            .assert()
+            .success();
+    }
+
     /// Write a `.pre-commit-config.yaml` file in the temporary directory.
     pub fn write_pre_commit_config(&self, content: &str) {
         self.temp_dir
@@ -0,0 +1,139 @@
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild, PathCreateDir};
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+#[test]
+fn doctor() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // The checks of git, docker and uv depend on the machine.
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(
+            r"(?m)^(ok|warning): (git|docker|podman|neither|uv) .*\n(  hint: .*\n)?",
+            "",
+        )])
+        .collect();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+
+    cmd_snapshot!(filters.clone(), context.doctor(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    ok: Config `.pre-commit-config.yaml` is valid
+    ok: 0 hook environments installed, 0 not installed yet
+
+    ----- stderr -----
+    "#);
+
+    // An interrupted install and a patch that was not restored.
+    context
+        .home_dir()
+        .child("hooks/0123456789abcdef")
+        .create_dir_all()?;
+    context
+        .home_dir()
+        .child("patches/1700000000000-1.patch")
+        .write_str("")?;
+
+    cmd_snapshot!(filters.clone(), context.doctor(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    ok: Config `.pre-commit-config.yaml` is valid
+    ok: 0 hook environments installed, 0 not installed yet
+    warning: 1 hook environments were not installed completely
+      hint: remove them from `[HOME]/hooks`, or run `prefligit clean` to remove all the environments
+    warning: 1 patches of non-staged changes could not be restored
+      hint: run `prefligit stash list` to see them, and `prefligit stash restore <id>` to apply them
+
+    ----- stderr -----
+    "#);
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+    "});
+
+    cmd_snapshot!(filters.clone(), context.doctor(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    error: Failed to parse `.pre-commit-config.yaml`: repos: Invalid local repo: missing field `hooks` at line 2 column 3
+      hint: fix the config, or run `prefligit sample-config` to start a new one
+    warning: 1 patches of non-staged changes could not be restored
+      hint: run `prefligit stash list` to see them, and `prefligit stash restore <id>` to apply them
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Repos that are not cloned yet are reported, and neither cloned nor checked against the remote.
+#[cfg(unix)]
+#[test]
+fn doctor_not_cloned() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(
+            r"(?m)^(ok|warning): (git|docker|podman|neither|uv) .*\n(  hint: .*\n)?",
+            "",
+        )])
+        .collect();
+
+    let repo = context.create_hook_repo()?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: {repo}
+            rev: v1.0.0
+            hooks:
+              - id: echo
+    "});
+
+    cmd_snapshot!(filters.clone(), context.doctor(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    ok: Config `.pre-commit-config.yaml` is valid
+    ok: Repos `file://[TEMP_DIR]/hook-repo@v1.0.0` are not cloned yet, their hooks are not checked
+    ok: 0 hook environments installed, 0 not installed yet
+
+    ----- stderr -----
+    "#);
+    assert!(!context.home_dir().child("repos").exists());
+
+    // Cloned by a run, the hooks are checked.
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add("file.txt");
+    context.run().assert().success();
+    cmd_snapshot!(filters.clone(), context.doctor(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    ok: Config `.pre-commit-config.yaml` is valid
+    ok: 0 hook environments installed, 0 not installed yet
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+#[test]
+fn explain() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        exclude: ^vendor/
+        repos:
+          - repo: local
+            hooks:
+              - id: fmt
+                name: format
+                language: system
+                entry: cargo fmt
+                types: [rust]
+              - id: python
+                name: python
+                language: system
+                entry: ruff
+                types: [python]
+                exclude_types: [rust]
+              - id: docs
+                name: docs
+                language: system
+                entry: cargo doc
+                files: ^docs/
+                exclude: \.rs$
+    "});
+    context
+        .workdir()
+        .child("src/main.rs")
+        .write_str("fn main() {}\n")?;
+    context.workdir().child("vendor/lib.rs").write_str("\n")?;
+
+    cmd_snapshot!(context.filters(), context.explain().arg("src/main.rs"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    src/main.rs
+      tags: file, non-executable, rust, text
+    fmt (local): runs
+    python (local): skipped
+      - missing `types`: python
+      - has `exclude_types`: rust
+    docs (local): skipped
+      - does not match `files: ^docs/`
+      - matches `exclude: \.rs$`
+
+    ----- stderr -----
+    "#);
+
+    // Excluded by the top-level config.
+    cmd_snapshot!(context.filters(), context.explain().arg("vendor/lib.rs"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    vendor/lib.rs
+      tags: file, non-executable, rust, text
+    No hook runs on it, it is skipped by the top-level config:
+      - matches `exclude: ^vendor/`
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.explain().arg("missing.rs"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    File `missing.rs` does not exist
+    "#);
+
+    Ok(())
+}
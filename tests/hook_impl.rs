@@ -1,5 +1,8 @@
+use std::fmt::Write;
 use std::process::Command;
 
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
 use common::TestContext;
 use indoc::indoc;
 
@@ -56,3 +59,386 @@ fn hook_impl() {
       .pre-commit-config.yaml
     "#);
 }
+
+/// `pre-push` hooks run on the files changed by all pushed refs.
+#[test]
+fn pre_push() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: fail
+             name: fail
+             language: fail
+             entry: always fail
+             stages: [pre-push]
+    "});
+    context.workdir().child("a.txt").write_str("a")?;
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    // Set up a remote that already has the initial commit.
+    Command::new("git")
+        .arg("init")
+        .arg("--bare")
+        .arg("--initial-branch=master")
+        .arg(".remote.git")
+        .current_dir(context.workdir())
+        .assert()
+        .success();
+    context
+        .git()
+        .args(["remote", "add", "origin", ".remote.git"])
+        .assert()
+        .success();
+    context
+        .git()
+        .args(["push", "--quiet", "origin", "master"])
+        .assert()
+        .success();
+
+    context
+        .install()
+        .args(["--hook-type", "pre-push"])
+        .assert()
+        .success();
+
+    // An existing branch and a new branch.
+    context.workdir().child("b.txt").write_str("b")?;
+    context.git_add("b.txt");
+    context.git_commit("Add b");
+    context
+        .git()
+        .args(["checkout", "--quiet", "-b", "feature"])
+        .assert()
+        .success();
+    context.workdir().child("c.txt").write_str("c")?;
+    context.git_add("c.txt");
+    context.git_commit("Add c");
+
+    let mut push = context.git();
+    push.args(["push", "--quiet", "origin", "master", "feature"]);
+
+    cmd_snapshot!(context.filters(), push, @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      always fail
+
+      c.txt
+      b.txt
+
+    ----- stderr -----
+    error: failed to push some refs to '.remote.git'
+    "#);
+
+    Ok(())
+}
+
+/// The hook moved aside by `prefligit install` still runs, and its failure fails the hook.
+#[cfg(unix)]
+#[test]
+fn legacy_hook() -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let context = TestContext::new();
+
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: echo
+             name: echo
+             language: system
+             entry: echo
+    "});
+    context.git_add(".");
+
+    let legacy = context.workdir().child(".git/hooks/pre-commit");
+    legacy.write_str(indoc! { r#"
+        #!/bin/sh
+        echo "legacy hook: $#"
+        exit 3
+    "#})?;
+    fs_err::set_permissions(&legacy, std::fs::Permissions::from_mode(0o755))?;
+
+    cmd_snapshot!(context.filters(), context.install(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Hook already exists at .git/hooks/pre-commit, move it to .git/hooks/pre-commit.legacy.
+    prefligit installed at .git/hooks/pre-commit
+
+    ----- stderr -----
+    "#);
+
+    let mut commit = context.git();
+    commit.args(["commit", "-m", "Initial commit"]);
+
+    cmd_snapshot!(context.filters(), commit, @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    legacy hook: 0
+    Legacy hook .git/hooks/pre-commit.legacy failed with exit code 3
+    echo.....................................................................Passed
+    "#);
+
+    Ok(())
+}
+
+/// A legacy hook that exits without reading its stdin doesn't fail the hook.
+#[cfg(unix)]
+#[test]
+fn legacy_hook_ignores_stdin() -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let context = TestContext::new();
+
+    context.init_project();
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: fail
+             name: fail
+             language: fail
+             entry: always fail
+             stages: [pre-push]
+    "});
+
+    let legacy = context.workdir().child(".git/hooks/pre-push.legacy");
+    legacy.write_str(indoc! { r"
+        #!/bin/sh
+        exit 0
+    "})?;
+    fs_err::set_permissions(&legacy, std::fs::Permissions::from_mode(0o755))?;
+
+    // Deleting many refs, more than fits in the pipe buffer.
+    let zero = "0".repeat(40);
+    let mut stdin = String::new();
+    for i in 0..20000 {
+        writeln!(stdin, "(delete) {zero} refs/heads/branch-{i} {zero}")?;
+    }
+    let stdin_file = context.workdir().child("stdin.txt");
+    stdin_file.write_str(&stdin)?;
+
+    cmd_snapshot!(context.filters(), context.command()
+        .args(["hook-impl", "--hook-type=pre-push", "--hook-dir", ".git/hooks", "--", "origin", "url"])
+        .stdin(fs_err::File::open(&*stdin_file)?.into_parts().0), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Legacy hook .git/hooks/pre-push.legacy passed
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Hooks installed from a linked worktree live in the common dir, and run in every worktree.
+#[test]
+fn worktree() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: echo
+             name: echo
+             language: system
+             entry: echo
+             verbose: true
+    "});
+    context.workdir().child("a.txt").write_str("a")?;
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    context
+        .git()
+        .args(["worktree", "add", "--quiet", "linked"])
+        .assert()
+        .success();
+    let linked = context.workdir().child("linked");
+
+    let mut install = context.install();
+    install.current_dir(&linked);
+    cmd_snapshot!(context.filters(), install, @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit installed at [TEMP_DIR]/.git/hooks/pre-commit
+
+    ----- stderr -----
+    "#);
+
+    // Non-staged changes of the linked worktree are saved and restored.
+    linked.child("a.txt").write_str("changed")?;
+    linked.child("b.txt").write_str("b")?;
+    context
+        .git()
+        .args(["add", "b.txt"])
+        .current_dir(&linked)
+        .assert()
+        .success();
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(
+            r"/[0-9a-f]{16}/\d+-\d+.patch",
+            "/[WORKTREE]/[TIME]-[PID].patch",
+        )])
+        .collect();
+
+    let mut commit = context.git();
+    commit
+        .args(["commit", "--quiet", "-m", "Add b"])
+        .current_dir(&linked);
+    cmd_snapshot!(filters, commit, @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    Non-staged changes detected, saving to `[HOME]/patches/[WORKTREE]/[TIME]-[PID].patch`
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      b.txt
+
+    Restored working tree changes from `[HOME]/patches/[WORKTREE]/[TIME]-[PID].patch`
+    "#);
+    assert_eq!(fs_err::read_to_string(linked.child("a.txt"))?, "changed");
+
+    Ok(())
+}
+
+/// Hooks in a submodule use the config of the submodule.
+#[test]
+fn submodule() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+    context.configure_git_author();
+
+    // The repo to use as the submodule.
+    let upstream = context.workdir().child("upstream");
+    Command::new("git")
+        .args(["init", "--quiet", "--initial-branch=master"])
+        .arg(&*upstream)
+        .assert()
+        .success();
+    upstream.child("a.txt").write_str("a")?;
+    upstream
+        .child(".pre-commit-config.yaml")
+        .write_str(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: submodule
+             name: submodule
+             language: system
+             entry: echo
+             verbose: true
+    "})?;
+    for args in [
+        &["config", "user.name", "Prefligit Test"][..],
+        &["config", "user.email", "test@prefligit.dev"],
+        &["add", "."],
+        &["commit", "--quiet", "-m", "Initial commit"],
+    ] {
+        context
+            .git()
+            .args(args)
+            .current_dir(&upstream)
+            .assert()
+            .success();
+    }
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: superproject
+             name: superproject
+             language: system
+             entry: echo
+             verbose: true
+    "});
+    context
+        .git()
+        .args([
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            "--quiet",
+        ])
+        .arg(&*upstream)
+        .arg("sub")
+        .assert()
+        .success();
+    let sub = context.workdir().child("sub");
+
+    let mut install = context.install();
+    install.current_dir(&sub);
+    cmd_snapshot!(context.filters(), install, @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit installed at [TEMP_DIR]/.git/modules/sub/hooks/pre-commit
+
+    ----- stderr -----
+    "#);
+
+    sub.child("b.txt").write_str("b")?;
+    for args in [
+        &["config", "user.name", "Prefligit Test"][..],
+        &["config", "user.email", "test@prefligit.dev"],
+        &["add", "b.txt"],
+    ] {
+        context
+            .git()
+            .args(args)
+            .current_dir(&sub)
+            .assert()
+            .success();
+    }
+
+    let mut commit = context.git();
+    commit
+        .args(["commit", "--quiet", "-m", "Add b"])
+        .current_dir(&sub);
+    cmd_snapshot!(context.filters(), commit, @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    submodule................................................................Passed
+    - hook id: submodule
+    - duration: [TIME]
+      b.txt
+    "#);
+
+    Ok(())
+}
@@ -234,6 +234,144 @@ fn uninstall() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Install into `core.hooksPath`, keeping the hooks already there.
+#[test]
+fn hooks_path() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context
+        .git()
+        .args(["config", "core.hooksPath", "custom-hooks"])
+        .assert()
+        .success();
+    context
+        .workdir()
+        .child("custom-hooks/pre-commit")
+        .write_str("#!/bin/sh\necho 'pre-commit'\n")?;
+
+    cmd_snapshot!(context.filters(), context.install(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Cowardly refusing to install hooks with `core.hooksPath` set.
+    hint: `git config --unset-all core.hooksPath` to fix this.
+    hint: or use `--hooks-path` to install into it.
+    "#);
+
+    cmd_snapshot!(context.filters(), context.install().arg("--hooks-path"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Hook already exists at custom-hooks/pre-commit, move it to custom-hooks/pre-commit.legacy.
+    prefligit installed at custom-hooks/pre-commit
+
+    ----- stderr -----
+    "#);
+    context
+        .workdir()
+        .child("custom-hooks/pre-commit.legacy")
+        .assert(predicate::path::exists());
+    context
+        .workdir()
+        .child(".git/hooks/pre-commit")
+        .assert(predicate::path::missing());
+
+    // Another tool replaced its hook again, don't lose the moved one.
+    context
+        .workdir()
+        .child("custom-hooks/pre-commit")
+        .write_str("#!/bin/sh\necho 'pre-commit'\n")?;
+    cmd_snapshot!(context.filters(), context.install().arg("--hooks-path"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Both custom-hooks/pre-commit and custom-hooks/pre-commit.legacy exist, refusing to replace them.
+    hint: remove one of them, or use `--overwrite` to replace custom-hooks/pre-commit.
+    "#);
+
+    context
+        .install()
+        .arg("--hooks-path")
+        .arg("--overwrite")
+        .assert()
+        .success();
+    cmd_snapshot!(context.filters(), context.uninstall(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    .git/hooks/pre-commit does not exist, skipping.
+    hint: use `--hooks-path` to uninstall from the `core.hooksPath` directory.
+    "#);
+    cmd_snapshot!(context.filters(), context.uninstall().arg("--hooks-path"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Uninstalled pre-commit
+    Restored previous hook to custom-hooks/pre-commit
+
+    ----- stderr -----
+    "#);
+    assert_snapshot!(context.read("custom-hooks/pre-commit"), @r##"
+    #!/bin/sh
+    echo 'pre-commit'
+    "##);
+
+    // A hooks directory outside the repository may be shared by other repositories.
+    context
+        .git()
+        .args(["config", "core.hooksPath", "../shared-hooks"])
+        .assert()
+        .success();
+    cmd_snapshot!(context.filters(), context.install().arg("--hooks-path"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    ../shared-hooks is outside the repository, the hooks will skip repositories without a config file.
+    prefligit installed at ../shared-hooks/pre-commit
+
+    ----- stderr -----
+    "#);
+    insta::with_settings!(
+        { filters => context.filters() },
+        {
+            assert_snapshot!(context.read("../shared-hooks/pre-commit"), @r##"
+            #!/usr/bin/env bash
+            # File generated by prefligit: https://github.com/j178/prefligit
+            # ID: 182c10f181da4464a3eec51b83331688
+
+            ARGS=(hook-impl --hook-type=pre-commit --skip-on-missing-config)
+
+            HERE="$(cd "$(dirname "$0")" && pwd)"
+            ARGS+=(--hook-dir "$HERE" -- "$@")
+            PREFLIGIT="[CURRENT_EXE]"
+
+            exec "$PREFLIGIT" "${ARGS[@]}"
+            "##);
+        }
+    );
+    cmd_snapshot!(context.filters(), context.uninstall().arg("--hooks-path"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Uninstalled pre-commit
+
+    ----- stderr -----
+    "#);
+    context
+        .workdir()
+        .child("../shared-hooks/pre-commit")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
 #[test]
 fn init_template_dir() {
     let context = TestContext::new();
@@ -0,0 +1,180 @@
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+#[test]
+fn list() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: fmt
+                name: format
+                alias: format
+                language: system
+                entry: cargo fmt
+                types: [rust]
+              - id: no-todo
+                name: no todo
+                language: fail
+                entry: todo found
+                files: ^src/
+                exclude: ^src/vendor/
+                stages: [pre-push]
+    "});
+
+    cmd_snapshot!(context.filters(), context.list(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    fmt (local)
+      name: format
+      alias: format
+      language: system (default)
+      stages: manual, commit-msg, post-checkout, post-commit, post-merge, post-rewrite, pre-commit, pre-merge-commit, pre-push, pre-rebase, prepare-commit-msg
+      files: (all)
+      exclude: (none)
+      types: rust
+      environment: not needed
+    no-todo (local)
+      name: no todo
+      language: fail (default)
+      stages: pre-push
+      files: ^src/
+      exclude: ^src/vendor/
+      types: file
+      environment: not needed
+
+    ----- stderr -----
+    "#);
+
+    // Only the hooks running in the stage.
+    cmd_snapshot!(context.filters(), context.list().arg("--stage").arg("pre-commit"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    fmt (local)
+      name: format
+      alias: format
+      language: system (default)
+      stages: manual, commit-msg, post-checkout, post-commit, post-merge, post-rewrite, pre-commit, pre-merge-commit, pre-push, pre-rebase, prepare-commit-msg
+      files: (all)
+      exclude: (none)
+      types: rust
+      environment: not needed
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.list().arg("--json"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [
+      {
+        "id": "fmt",
+        "name": "format",
+        "alias": "format",
+        "repo": "local",
+        "language": "system",
+        "language_version": "default",
+        "resolved_version": null,
+        "stages": [
+          "manual",
+          "commit-msg",
+          "post-checkout",
+          "post-commit",
+          "post-merge",
+          "post-rewrite",
+          "pre-commit",
+          "pre-merge-commit",
+          "pre-push",
+          "pre-rebase",
+          "prepare-commit-msg"
+        ],
+        "files": null,
+        "exclude": null,
+        "types": [
+          "rust"
+        ],
+        "types_or": [],
+        "exclude_types": [],
+        "env_path": null,
+        "installed": true
+      },
+      {
+        "id": "no-todo",
+        "name": "no todo",
+        "alias": "",
+        "repo": "local",
+        "language": "fail",
+        "language_version": "default",
+        "resolved_version": null,
+        "stages": [
+          "pre-push"
+        ],
+        "files": "^src/",
+        "exclude": "^src/vendor/",
+        "types": [
+          "file"
+        ],
+        "types_or": [],
+        "exclude_types": [],
+        "env_path": null,
+        "installed": true
+      }
+    ]
+
+    ----- stderr -----
+    "#);
+}
+
+/// The version from the lockfile is shown next to the requested one.
+#[test]
+fn list_resolved_version() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: python
+                language_version: '3.12'
+                entry: lint
+    "});
+    context
+        .workdir()
+        .child(".pre-commit-lock.yaml")
+        .write_str(indoc::indoc! {r"
+            hooks:
+            - repo: local
+              id: lint
+              language_version: '3.12'
+              resolved_version: 3.12.4
+        "})
+        .unwrap();
+
+    cmd_snapshot!(context.filters(), context.list(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    lint (local)
+      name: lint
+      language: python (3.12, resolved 3.12.4)
+      stages: manual, commit-msg, post-checkout, post-commit, post-merge, post-rewrite, pre-commit, pre-merge-commit, pre-push, pre-rebase, prepare-commit-msg
+      files: (all)
+      exclude: (none)
+      types: file
+      environment: not installed, will be created at [HOME]/hooks/bd3e392c998a3555
+
+    ----- stderr -----
+    "#);
+}
@@ -0,0 +1,82 @@
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+use insta::assert_snapshot;
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+/// Repos are cloned at the locked commit even after their tag moves.
+// `file://` URLs of the hook repo are written for unix paths.
+#[cfg(unix)]
+#[test]
+fn lock() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_hook_repo()?;
+
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add("file.txt");
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: {repo}
+            rev: v1.0.0
+            hooks:
+              - id: echo
+    "});
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"[0-9a-f]{40}", "[SHA]")])
+        .collect();
+
+    cmd_snapshot!(filters.clone(), context.lock(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Locked 1 repos and 0 hooks in `.pre-commit-lock.yaml`
+
+    ----- stderr -----
+    "#);
+
+    insta::with_settings!(
+        { filters => filters.clone() },
+        {
+            assert_snapshot!(context.read(".pre-commit-lock.yaml"), @r#"
+            # Generated by `prefligit lock`, do not edit it by hand.
+            repos:
+            - repo: file://[TEMP_DIR]/hook-repo
+              rev: v1.0.0
+              sha: [SHA]
+            hooks: []
+            "#);
+        }
+    );
+
+    // Move the tag to a commit renaming the hook.
+    context.move_hook_repo_tag("moved")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    // Relocking picks up the moved tag.
+    context.lock().assert().success();
+    context.clean().assert().success();
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    moved....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use constants::env_vars::EnvVars;
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+/// Repos are cloned from the mirror their URL is rewritten to, a local repo stands in for it.
+// `file://` URLs of the hook repo are written for unix paths.
+#[cfg(unix)]
+#[test]
+fn repo_mirror() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let mirror = context.create_hook_repo()?;
+    let mirror = mirror.trim_end_matches("hook-repo");
+
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add("file.txt");
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://git.example.com/hook-repo
+            rev: v1.0.0
+            hooks:
+              - id: echo
+    "});
+
+    let settings = context.workdir().child("settings.yaml");
+    settings.write_str(&indoc::formatdoc! {r"
+        mirrors:
+          repos:
+            - prefix: https://git.example.com/
+              replacement: {mirror}
+    "})?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files").env(EnvVars::PREFLIGIT_SETTINGS, &*settings), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    // The environment variable takes precedence over the settings file.
+    context.clean().assert().success();
+    cmd_snapshot!(context.filters(), context.run()
+        .arg("--all-files")
+        .env(EnvVars::PREFLIGIT_SETTINGS, &*settings)
+        .env(EnvVars::PREFLIGIT_REPO_MIRRORS, "https://git.example.com/=file:///nonexistent/"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: failed to git full clone (status: exit status: 128)
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run()
+        .arg("--all-files")
+        .env(EnvVars::PREFLIGIT_SETTINGS, &*settings)
+        .env(EnvVars::PREFLIGIT_REPO_MIRRORS, "https://git.example.com/"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Invalid `PREFLIGIT_REPO_MIRRORS`: expected space separated `<prefix>=<replacement>` pairs, got `https://git.example.com/`
+    "#);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_settings() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config("repos: []");
+
+    let settings = context.workdir().child("settings.yaml");
+    settings.write_str(indoc::indoc! {r"
+        mirrors:
+          node: https://mirror.example.com/nodejs/dist
+        proxies: http://proxy.example.com:3128
+    "})?;
+
+    cmd_snapshot!(context.filters(), context.run().env(EnvVars::PREFLIGIT_SETTINGS, &*settings), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Invalid settings file `settings.yaml`
+      caused by: unknown field `proxies`, expected one of `mirrors`, `proxy`, `no_proxy`, `ca_cert`, `strict_revs` at line 3 column 1
+    "#);
+
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use constants::env_vars::EnvVars;
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+fn write_config(context: &TestContext, repo: &str, rev: &str) -> anyhow::Result<()> {
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add("file.txt");
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: {repo}
+            rev: {rev}
+            hooks:
+              - id: echo
+    "});
+    Ok(())
+}
+
+/// Pretend the revs of the cloned repos were last checked against the remote two days ago.
+fn expire_revalidation(context: &TestContext) -> anyhow::Result<()> {
+    let checked = SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60);
+    for entry in fs_err::read_dir(context.home_dir().child("repos").path())? {
+        let resolved = entry?.path().join(".resolved_rev");
+        if resolved.exists() {
+            fs_err::File::options()
+                .write(true)
+                .open(resolved)?
+                .file()
+                .set_modified(checked)?;
+        }
+    }
+    Ok(())
+}
+
+/// The clones in the store, without the lock files next to them.
+fn clones(context: &TestContext) -> anyhow::Result<Vec<PathBuf>> {
+    let mut clones = Vec::new();
+    for entry in fs_err::read_dir(context.home_dir().child("repos").path())? {
+        let path = entry?.path();
+        if path.is_dir() {
+            clones.push(path);
+        }
+    }
+    Ok(clones)
+}
+
+// `file://` URLs of the hook repo are written for unix paths.
+#[cfg(unix)]
+#[test]
+fn branch_rev() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_hook_repo()?;
+    write_config(&context, &repo, "master")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    warning: Repo `file://[TEMP_DIR]/hook-repo@master` uses branch `master` as `rev`, which moves with every push, pin it to a tag or a commit
+    "#);
+
+    Ok(())
+}
+
+/// A moved tag is noticed once the clone is due to be checked against the remote again.
+#[cfg(unix)]
+#[test]
+fn moved_tag() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_hook_repo()?;
+    write_config(&context, &repo, "v1.0.0")?;
+
+    context.run().arg("--all-files").assert().success();
+    context.move_hook_repo_tag("moved")?;
+
+    // Checked recently, the clone is used as is.
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    // Not checked offline.
+    expire_revalidation(&context)?;
+    cmd_snapshot!(context.filters(), context.run().arg("--offline").arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"[0-9a-f]{40}", "[SHA]")])
+        .collect();
+    cmd_snapshot!(filters, context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    moved....................................................................Passed
+
+    ----- stderr -----
+    warning: Tag `v1.0.0` of `file://[TEMP_DIR]/hook-repo` moved from `[SHA]` to `[SHA]`, cloning it again
+    "#);
+
+    // The moved commit is cloned next to the clone in use, which is left as is.
+    assert_eq!(clones(&context)?.len(), 2);
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    moved....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Repos cloned before revs were recorded are checked against the remote on first use.
+#[cfg(unix)]
+#[test]
+fn unrecorded_rev() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_hook_repo()?;
+    write_config(&context, &repo, "v1.0.0")?;
+
+    context.run().arg("--all-files").assert().success();
+    let [clone] = &clones(&context)?[..] else {
+        anyhow::bail!("Expected one clone");
+    };
+    fs_err::remove_file(clone.join(".resolved_rev"))?;
+    context.move_hook_repo_tag("moved")?;
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"[0-9a-f]{40}", "[SHA]")])
+        .collect();
+    cmd_snapshot!(filters, context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    moved....................................................................Passed
+
+    ----- stderr -----
+    warning: Tag `v1.0.0` of `file://[TEMP_DIR]/hook-repo` moved from `[SHA]` to `[SHA]`, cloning it again
+    "#);
+    assert!(
+        context
+            .read(clone.join(".resolved_rev"))
+            .starts_with("tag ")
+    );
+
+    Ok(())
+}
+
+/// An unreachable remote is tried once per interval, not on every run.
+#[cfg(unix)]
+#[test]
+fn unreachable_remote() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_hook_repo()?;
+    write_config(&context, &repo, "v1.0.0")?;
+
+    context.run().arg("--all-files").assert().success();
+    let [clone] = &clones(&context)?[..] else {
+        anyhow::bail!("Expected one clone");
+    };
+    let hook_repo = context.workdir().child("hook-repo");
+    let moved_away = context.workdir().child("hook-repo-moved-away");
+
+    // Both an expired and an unrecorded rev are checked, and the attempt is recorded.
+    for unrecorded in [false, true] {
+        if unrecorded {
+            fs_err::remove_file(clone.join(".resolved_rev"))?;
+        } else {
+            expire_revalidation(&context)?;
+        }
+        fs_err::rename(&hook_repo, &moved_away)?;
+        context.run().arg("--all-files").assert().success();
+        fs_err::rename(&moved_away, &hook_repo)?;
+
+        let checked = fs_err::metadata(clone.join(".resolved_rev"))?.modified()?;
+        assert!(checked.elapsed()? < Duration::from_secs(60 * 60));
+    }
+    assert!(
+        context
+            .read(clone.join(".resolved_rev"))
+            .starts_with("unresolved ")
+    );
+
+    // Reachable again, but not checked until the interval passed.
+    context.move_hook_repo_tag("moved")?;
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    expire_revalidation(&context)?;
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"[0-9a-f]{40}", "[SHA]")])
+        .collect();
+    cmd_snapshot!(filters, context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    moved....................................................................Passed
+
+    ----- stderr -----
+    warning: Tag `v1.0.0` of `file://[TEMP_DIR]/hook-repo` moved from `[SHA]` to `[SHA]`, cloning it again
+    "#);
+    assert!(
+        context
+            .read(clone.join(".resolved_rev"))
+            .starts_with("tag ")
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn strict_revs() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_hook_repo()?;
+    write_config(&context, &repo, "v1.0.0")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files").env(EnvVars::PREFLIGIT_STRICT_REVS, "1"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Repo `file://[TEMP_DIR]/hook-repo@v1.0.0` is not pinned to a full commit SHA, which strict revs require
+    hint: pin `rev` to a commit, or lock it with `prefligit lock`
+    "#);
+
+    // The lockfile pins the tag to a commit.
+    context.lock().assert().success();
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files").env(EnvVars::PREFLIGIT_STRICT_REVS, "1"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
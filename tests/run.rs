@@ -5,6 +5,8 @@ use assert_cmd::assert::OutputAssertExt;
 use assert_fs::prelude::*;
 use insta::assert_snapshot;
 
+use constants::env_vars::EnvVars;
+
 use crate::common::{TestContext, cmd_snapshot};
 
 mod common;
@@ -682,6 +684,62 @@ fn log_file() {
     assert_eq!(log, "Fixing files");
 }
 
+/// Run hooks under a pseudo-terminal with `--pty`, and force colors when enabled.
+#[cfg(unix)]
+#[test]
+fn pty() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: colors
+                name: colors
+                language: system
+                entry: sh -c '[ -t 1 ] && echo terminal || echo pipe; echo "force=$FORCE_COLOR,$CLICOLOR_FORCE"; printf "\033[31mred\033[0m\n"; exit 1' --
+                always_run: true
+                log_file: log.txt
+    "#});
+    context.git_add(".");
+
+    // The log file has colors stripped.
+    context
+        .run()
+        .args(["--color", "always", "--pty"])
+        .assert()
+        .failure();
+    assert_snapshot!(context.read("log.txt"), @r#"
+    terminal
+    force=1,1
+    red
+    "#);
+    fs_err::remove_file(context.workdir().join("log.txt"))?;
+
+    context.run().args(["--color", "always"]).assert().failure();
+    assert_snapshot!(context.read("log.txt"), @r#"
+    pipe
+    force=1,1
+    red
+    "#);
+    fs_err::remove_file(context.workdir().join("log.txt"))?;
+
+    // No pseudo-terminal without colors.
+    context
+        .run()
+        .args(["--color", "never", "--pty"])
+        .assert()
+        .failure();
+    assert_snapshot!(context.read("log.txt"), @r#"
+    pipe
+    force=,
+    red
+    "#);
+
+    Ok(())
+}
+
 /// Pass pre-commit environment variables to the hook.
 #[test]
 fn pass_env_vars() {
@@ -713,6 +771,167 @@ fn pass_env_vars() {
     "###);
 }
 
+/// Hooks run after the hooks in their `after` and `requires`, and are skipped when a required hook fails.
+#[test]
+fn hook_dependencies() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo lint
+                requires: [format]
+                group: lint
+                always_run: true
+                verbose: true
+              - id: typecheck
+                name: typecheck
+                language: system
+                entry: echo typecheck
+                requires: [lint]
+                group: [lint, types]
+                always_run: true
+                verbose: true
+              - id: format
+                name: format
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1]); sys.exit(int(sys.argv[1] == "fail"))'
+                args: [ok]
+                after: [docs]
+                always_run: true
+                verbose: true
+              - id: docs
+                name: docs
+                language: system
+                entry: echo docs
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    docs.....................................................................Passed
+    - hook id: docs
+    - duration: [TIME]
+      docs .pre-commit-config.yaml
+    format...................................................................Passed
+    - hook id: format
+    - duration: [TIME]
+      ok
+    lint.....................................................................Passed
+    - hook id: lint
+    - duration: [TIME]
+      lint .pre-commit-config.yaml
+    typecheck................................................................Passed
+    - hook id: typecheck
+    - duration: [TIME]
+      typecheck .pre-commit-config.yaml
+
+    ----- stderr -----
+    "#);
+
+    // Only the hooks in the group run.
+    cmd_snapshot!(context.filters(), context.run().arg("--group").arg("lint"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    lint.....................................................................Passed
+    - hook id: lint
+    - duration: [TIME]
+      lint .pre-commit-config.yaml
+    typecheck................................................................Passed
+    - hook id: typecheck
+    - duration: [TIME]
+      typecheck .pre-commit-config.yaml
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--group").arg("missing"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    No hook found in group `missing`
+    "#);
+
+    // Hooks requiring a failed hook are skipped.
+    context.write_pre_commit_config(
+        &context
+            .read(".pre-commit-config.yaml")
+            .replace("[ok]", "[fail]"),
+    );
+    context.git_add(".");
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    docs.....................................................................Passed
+    - hook id: docs
+    - duration: [TIME]
+      docs .pre-commit-config.yaml
+    format...................................................................Failed
+    - hook id: format
+    - duration: [TIME]
+    - exit code: 1
+      fail
+    lint..............................................(required hook failed)Skipped
+    typecheck.........................................(required hook failed)Skipped
+
+    ----- stderr -----
+    "#);
+}
+
+/// Set environment variables for hooks with `env` and `default_env`.
+#[test]
+fn hook_env() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        default_env:
+          GREETING: hello
+          TARGET: world
+        repos:
+          - repo: local
+            hooks:
+              - id: env
+                name: env
+                language: system
+                entry: python3 -c "import os; print(os.environ['GREETING'], os.environ['TARGET'], repr(os.environ['UNSET']))"
+                env:
+                  TARGET: ${PREFLIGIT_TEST_NAME}!
+                  UNSET: ${PREFLIGIT_TEST_UNSET}
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    let mut run = context.run();
+    run.env("PREFLIGIT_TEST_NAME", "prefligit")
+        .env_remove("PREFLIGIT_TEST_UNSET");
+    cmd_snapshot!(context.filters(), run, @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    env......................................................................Passed
+    - hook id: env
+    - duration: [TIME]
+      hello prefligit! ''
+
+    ----- stderr -----
+    "#);
+}
+
 #[test]
 fn staged_files_only() -> Result<()> {
     let context = TestContext::new();
@@ -744,7 +963,10 @@ fn staged_files_only() -> Result<()> {
     let filters: Vec<_> = context
         .filters()
         .into_iter()
-        .chain([(r"/\d+-\d+.patch", "/[TIME]-[PID].patch")])
+        .chain([(
+            r"/[0-9a-f]{16}/\d+-\d+.patch",
+            "/[WORKTREE]/[TIME]-[PID].patch",
+        )])
         .collect();
 
     cmd_snapshot!(filters, context.run(), @r#"
@@ -757,9 +979,9 @@ fn staged_files_only() -> Result<()> {
       Hello, world!
 
     ----- stderr -----
-    Non-staged changes detected, saving to `[HOME]/patches/[TIME]-[PID].patch`
+    Non-staged changes detected, saving to `[HOME]/patches/[WORKTREE]/[TIME]-[PID].patch`
 
-    Restored working tree changes from `[HOME]/patches/[TIME]-[PID].patch`
+    Restored working tree changes from `[HOME]/patches/[WORKTREE]/[TIME]-[PID].patch`
     "#);
 
     let content = context.read("file.txt");
@@ -768,6 +990,251 @@ fn staged_files_only() -> Result<()> {
     Ok(())
 }
 
+/// Non-staged changes are merged with the changes made by hooks.
+#[test]
+fn restore_with_hook_changes() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: strip
+                name: strip
+                language: system
+                entry: sed -i 's/ $//'
+                files: file.txt
+   "});
+
+    context
+        .workdir()
+        .child("file.txt")
+        .write_str("first \n2\n3\n4\n5\nlast\n")?;
+    context.git_add(".");
+    context
+        .workdir()
+        .child("file.txt")
+        .write_str("first \n2\n3\n4\n5\nlast changed\n")?;
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(
+            r"/[0-9a-f]{16}/\d+-\d+.patch",
+            "/[WORKTREE]/[TIME]-[PID].patch",
+        )])
+        .collect();
+
+    cmd_snapshot!(filters, context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    strip....................................................................Failed
+    - hook id: strip
+    - files were modified by this hook
+
+    ----- stderr -----
+    Non-staged changes detected, saving to `[HOME]/patches/[WORKTREE]/[TIME]-[PID].patch`
+
+    Restored working tree changes from `[HOME]/patches/[WORKTREE]/[TIME]-[PID].patch`
+    "#);
+
+    let content = context.read("file.txt");
+    assert_snapshot!(content, @r#"
+    first
+    2
+    3
+    4
+    5
+    last changed
+    "#);
+
+    Ok(())
+}
+
+/// Files are split into batches that fit in the command line length limit.
+#[test]
+fn batches() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: count
+                name: count
+                language: system
+                entry: python3 -c 'import sys; print(len(sys.argv) - 1)'
+                files: ^file_
+                verbose: true
+   "});
+    for i in 0..20 {
+        context
+            .workdir()
+            .child(format!("file_{i:02}.txt"))
+            .write_str("content")?;
+    }
+    context.git_add(".");
+
+    let mut run = context.run();
+    run.env(EnvVars::PREFLIGIT_INTERNAL__MAX_CLI_LENGTH, "1280")
+        .env(EnvVars::PREFLIGIT_NO_CONCURRENCY, "1");
+    cmd_snapshot!(context.filters(), run, @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    count....................................................................Passed
+    - hook id: count
+    - duration: [TIME]
+      9
+      9
+      2
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Output is streamed line by line with `--stream`, with stdout and stderr in the order written.
+#[test]
+fn stream_output() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: mixed
+                name: mixed
+                language: system
+                entry: sh -c 'echo out1; echo err1 >&2; echo out2; exit 1' --
+                pass_filenames: false
+                always_run: true
+   "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    mixed....................................................................Failed
+    - hook id: mixed
+    - exit code: 1
+      out1
+      err1
+      out2
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--stream"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    [mixed] out1
+    [mixed] err1
+    [mixed] out2
+    mixed....................................................................Failed
+    - hook id: mixed
+    - exit code: 1
+
+    ----- stderr -----
+    "#);
+}
+
+/// Hooks that run in-process don't stream, their output is shown after the status line.
+#[test]
+fn stream_in_process_output() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: fail
+                name: fail
+                language: fail
+                entry: always fails
+          - repo: meta
+            hooks:
+              - id: identity
+   "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--stream"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      always fails
+
+      .pre-commit-config.yaml
+    identity.................................................................Passed
+    - hook id: identity
+    - duration: [TIME]
+      .pre-commit-config.yaml
+
+    ----- stderr -----
+    "#);
+}
+
+/// Thousands of intent-to-add files are removed from and restored to the index in batches.
+#[test]
+fn many_intent_to_add_files() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: count
+                name: count
+                language: system
+                entry: python3 -c 'import sys; print(len(sys.argv) - 1)'
+                pass_filenames: false
+                always_run: true
+                verbose: true
+   "});
+    context.git_add(".pre-commit-config.yaml");
+
+    for i in 0..3000 {
+        context
+            .workdir()
+            .child(format!("intent-to-add/some/nested/directory/file_{i}.txt"))
+            .write_str("content")?;
+    }
+    context
+        .git()
+        .args(["add", "--intent-to-add", "intent-to-add"])
+        .assert()
+        .success();
+
+    // A small command line limit to split the files into many batches.
+    cmd_snapshot!(context.filters(), context.run().env(EnvVars::PREFLIGIT_INTERNAL__MAX_CLI_LENGTH, "4096"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    count....................................................................Passed
+    - hook id: count
+    - duration: [TIME]
+      0
+
+    ----- stderr -----
+    "#);
+
+    // All intent-to-add files are back in the index.
+    let output = context
+        .git()
+        .args(["diff", "--name-only", "--diff-filter=A"])
+        .output()?;
+    assert_eq!(String::from_utf8(output.stdout)?.lines().count(), 3000);
+
+    Ok(())
+}
+
 #[cfg(unix)]
 #[test]
 fn restore_on_interrupt() -> Result<()> {
@@ -968,3 +1435,236 @@ fn alternate_config_file() -> Result<()> {
 
     Ok(())
 }
+
+/// `check-ast` and `debug-statements` run natively, and reject code Python can't parse.
+// `file://` URLs of the hook repo are written for unix paths.
+#[cfg(unix)]
+#[test]
+fn builtin_python_source_hooks() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_pre_commit_hooks_repo(
+        "v5.0.0",
+        &[("check-ast", "[python]"), ("debug-statements", "[python]")],
+    )?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        pre_commit_hooks_mirrors: [{repo}]
+        repos:
+          - repo: {repo}
+            rev: v5.0.0
+            hooks:
+              - id: check-ast
+              - id: debug-statements
+    "});
+
+    let cwd = context.workdir();
+    cwd.child("valid.py")
+        .write_str("import os\n\ndef f(a, b=1):\n    breakpoint()\n")?;
+    cwd.child("invalid.py")
+        .write_str("x = 1\ndef f(a=1, b): pass\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    check-ast................................................................Failed
+    - hook id: check-ast
+    - exit code: 1
+      invalid.py:2:12: failed parsing: non-default argument follows default argument
+    debug-statements.........................................................Failed
+    - hook id: debug-statements
+    - exit code: 1
+      valid.py:4:4: breakpoint called
+      invalid.py - Could not parse ast
+
+      	invalid.py:2:12: non-default argument follows default argument
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// The shebang hooks check the mode in the git index, not the one on disk.
+#[cfg(unix)]
+#[test]
+fn builtin_shebang_hooks() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let context = TestContext::new();
+    context.init_project();
+    let repo = context.create_pre_commit_hooks_repo(
+        "v5.0.0",
+        &[
+            ("check-executables-have-shebangs", "[text, executable]"),
+            ("check-shebang-scripts-are-executable", "[text]"),
+        ],
+    )?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        pre_commit_hooks_mirrors: [{repo}]
+        repos:
+          - repo: {repo}
+            rev: v5.0.0
+            hooks:
+              - id: check-executables-have-shebangs
+              - id: check-shebang-scripts-are-executable
+    "});
+    // Like on Windows, the mode on disk is not tracked.
+    context
+        .git()
+        .args(["config", "core.fileMode", "false"])
+        .assert()
+        .success();
+
+    let cwd = context.workdir();
+    cwd.child("script.sh")
+        .write_str("#!/bin/sh\necho hello\n")?;
+    cwd.child("notes.txt").write_str("hello\n")?;
+    for file in ["script.sh", "notes.txt"] {
+        fs_err::set_permissions(cwd.child(file), std::fs::Permissions::from_mode(0o755))?;
+    }
+    context.git_add(".");
+    context
+        .git()
+        .args(["update-index", "--chmod=+x", "notes.txt"])
+        .assert()
+        .success();
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    check-executables-have-shebangs..........................................Failed
+    - hook id: check-executables-have-shebangs
+    - exit code: 1
+      notes.txt: marked executable but has no (or invalid) shebang!
+        If it isn't supposed to be executable, try: `chmod -x notes.txt`
+        If on Windows, you may also need to: `git update-index --chmod=-x notes.txt`
+        If it is supposed to be executable, double-check its shebang.
+    check-shebang-scripts-are-executable.....................................Failed
+    - hook id: check-shebang-scripts-are-executable
+    - exit code: 1
+      script.sh: has a shebang but is not marked executable!
+        If it is supposed to be executable, try: `chmod +x script.sh`
+        If on Windows, you may also need to: `git update-index --chmod=+x script.sh`
+        If it is not supposed to be executable, double-check its shebang is wanted.
+
+    ----- stderr -----
+    "#);
+
+    // Fixed in the index only, the mode on disk is swapped.
+    context
+        .git()
+        .args(["update-index", "--chmod=+x", "script.sh"])
+        .assert()
+        .success();
+    context
+        .git()
+        .args(["update-index", "--chmod=-x", "notes.txt"])
+        .assert()
+        .success();
+    fs_err::set_permissions(
+        cwd.child("script.sh"),
+        std::fs::Permissions::from_mode(0o644),
+    )?;
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    check-executables-have-shebangs..........................................Passed
+    check-shebang-scripts-are-executable.....................................Passed
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// The builtin hooks are only used for `pre-commit-hooks` and its mirrors, and fall back to
+/// the Python hook when they may behave differently.
+#[cfg(unix)]
+#[test]
+fn builtin_hooks_fallback() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    let repo =
+        context.create_pre_commit_hooks_repo("v5.0.0", &[("trailing-whitespace", "[text]")])?;
+    Command::new("git")
+        .args(["tag", "v2.0.0"])
+        .current_dir(context.workdir().child("pre-commit-hooks"))
+        .assert()
+        .success();
+    let config = indoc::formatdoc! {r"
+        repos:
+          - repo: {repo}
+            rev: v5.0.0
+            hooks:
+              - id: trailing-whitespace
+              - id: trailing-whitespace
+                name: unknown args
+                args: [--unknown]
+              - id: trailing-whitespace
+                name: env
+                env:
+                  FOO: bar
+          - repo: {repo}
+            rev: v2.0.0
+            hooks:
+              - id: trailing-whitespace
+                name: old rev
+    "};
+    context.write_pre_commit_config(&format!("pre_commit_hooks_mirrors: [{repo}]\n{config}"));
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add("file.txt");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trailing-whitespace......................................................Passed
+    unknown args.............................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      python hook
+    env......................................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      python hook
+    old rev..................................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      python hook
+
+    ----- stderr -----
+    "#);
+
+    // Not a known mirror, the Python hook is used.
+    context.write_pre_commit_config(&config);
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trailing-whitespace......................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      python hook
+    unknown args.............................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      python hook
+    env......................................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      python hook
+    old rev..................................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      python hook
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+use insta::assert_snapshot;
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+/// Changes conflicting with the hook are kept as a patch, and can be restored with `prefligit stash`.
+#[test]
+fn stash() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: strip
+                name: strip
+                language: system
+                entry: sed -i 's/ $//'
+                files: file.txt
+    "});
+    context
+        .workdir()
+        .child("file.txt")
+        .write_str("first\nlast \n")?;
+    context.git_add(".");
+    context
+        .workdir()
+        .child("file.txt")
+        .write_str("first\nlast changed \n")?;
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([
+            (r"/[0-9a-f]{16}/\d+-\d+.patch", "/[WORKTREE]/[ID].patch"),
+            (r"\d+-\d+  \d+ \w+ ago", "[ID]  [AGE]"),
+            (r"`\d+-\d+`", "`[ID]`"),
+        ])
+        .collect();
+
+    cmd_snapshot!(filters.clone(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    strip....................................................................Failed
+    - hook id: strip
+    - files were modified by this hook
+
+    ----- stderr -----
+    Non-staged changes detected, saving to `[HOME]/patches/[WORKTREE]/[ID].patch`
+
+    Restored working tree changes from `[HOME]/patches/[WORKTREE]/[ID].patch` with conflicts, resolve the conflict markers in the files
+    "#);
+    assert_snapshot!(context.read("file.txt"), @r#"
+    first
+    <<<<<<< ours
+    last
+    =======
+    last changed 
+    >>>>>>> theirs
+    "#);
+
+    cmd_snapshot!(filters.clone(), context.command().arg("stash").arg("list"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [ID]  [AGE]  [TEMP_DIR]/
+
+    ----- stderr -----
+    "#);
+
+    // Restore the saved changes over the hook's changes.
+    context
+        .git()
+        .args(["checkout", "--", "file.txt"])
+        .assert()
+        .success();
+    let output = context.command().arg("stash").arg("list").output()?;
+    let id = String::from_utf8(output.stdout)?
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .to_string();
+
+    cmd_snapshot!(filters.clone(), context.command().arg("stash").arg("restore").arg(&id), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Restored working tree changes from `[ID]`
+
+    ----- stderr -----
+    "#);
+    assert_snapshot!(context.read("file.txt"), @r#"
+    first
+    last changed
+    "#);
+
+    // The patch is removed once restored.
+    cmd_snapshot!(filters.clone(), context.command().arg("stash").arg("show").arg(&id), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    No saved patch with id `[ID]`
+    hint: run `prefligit stash list` to see the saved patches
+    "#);
+
+    Ok(())
+}
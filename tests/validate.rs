@@ -57,6 +57,135 @@ fn validate_config() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Hooks can only depend on hooks in the config, without cycles.
+#[test]
+fn validate_config_dependencies() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context
+        .workdir()
+        .child("unknown.yaml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: local
+                hooks:
+                  - id: lint
+                    name: lint
+                    language: system
+                    entry: lint
+                    requires: [format]
+        "})?;
+    context
+        .workdir()
+        .child("cycle.yaml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: local
+                hooks:
+                  - id: format
+                    name: format
+                    language: system
+                    entry: format
+                    after: [lint]
+                  - id: lint
+                    name: lint
+                    language: system
+                    entry: lint
+                    requires: [check]
+                  - id: check
+                    name: check
+                    language: system
+                    entry: check
+                    after: [format]
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("unknown.yaml").arg("cycle.yaml"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Hook `lint` in `unknown.yaml` depends on unknown hook `format`
+    error: Hooks in `cycle.yaml` depend on each other: check -> format -> lint -> check
+    "#);
+
+    Ok(())
+}
+
+/// Only hooks of languages that run with `env` can set it.
+#[test]
+fn validate_config_env() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context
+        .workdir()
+        .child("local.yaml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: local
+                hooks:
+                  - id: lint
+                    name: lint
+                    language: node
+                    entry: lint
+                    env:
+                      DEBUG: '1'
+        "})?;
+    context
+        .workdir()
+        .child("remote.yaml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: https://github.com/pre-commit/mirrors-prettier
+                rev: v3.1.0
+                hooks:
+                  - id: prettier
+                    language: node
+                    env:
+                      DEBUG: '1'
+        "})?;
+    context
+        .workdir()
+        .child("meta.yaml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: meta
+                hooks:
+                  - id: identity
+                    env:
+                      DEBUG: '1'
+        "})?;
+    // `default_env` only applies to the hooks that run with it.
+    context
+        .workdir()
+        .child("default.yaml")
+        .write_str(indoc::indoc! {r"
+            default_env:
+              DEBUG: '1'
+            repos:
+              - repo: local
+                hooks:
+                  - id: lint
+                    name: lint
+                    language: node
+                    entry: lint
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("local.yaml").arg("remote.yaml").arg("meta.yaml").arg("default.yaml"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Hook `lint` in `local.yaml` sets `env`, but `node` hooks do not run with it
+    error: Hook `prettier` in `remote.yaml` sets `env`, but `node` hooks do not run with it
+    error: Failed to parse `meta.yaml`
+      caused by: repos: Invalid meta repo: env is not allowed for meta hook at line 2 column 3
+    "#);
+
+    Ok(())
+}
+
 #[test]
 fn validate_manifest() -> anyhow::Result<()> {
     let context = TestContext::new();